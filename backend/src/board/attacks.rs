@@ -0,0 +1,524 @@
+use crate::board::board_info::Bitboard;
+use crate::board::piece::PieceKind;
+use crate::game::player::Color;
+use std::sync::OnceLock;
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_STEPS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = step_attacks(&KNIGHT_STEPS);
+const KING_ATTACKS: [Bitboard; 64] = step_attacks(&KING_STEPS);
+// Squares a pawn attacks, for White and for Black
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    step_attacks(&[(-1, 1), (1, 1)]),
+    step_attacks(&[(-1, -1), (1, -1)]),
+];
+
+// Magic multipliers of rooks and bishops for every square, found once by trying sparse random numbers
+const ROOK_MAGICS: [u64; 64] = [
+    0x0980_0080_1140_0020,
+    0x8340_0044_1000_2000,
+    0x0880_2000_9000_8268,
+    0x0080_0800_8010_0004,
+    0x8100_1100_0402_0800,
+    0x0300_0100_0400_0822,
+    0x0880_1A00_2900_0080,
+    0x8100_0500_0120_4882,
+    0x0844_8000_8140_0320,
+    0x0804_4020_1000_4000,
+    0x0108_8020_0310_0480,
+    0x0004_8080_0800_1000,
+    0x0003_0018_0100_1014,
+    0x0002_0002_0004_1008,
+    0x0004_0081_0804_2210,
+    0x0105_0001_0000_9042,
+    0x0400_8080_0040_0021,
+    0xC100_4040_1000_2000,
+    0x0060_0080_1000_2088,
+    0x0400_8080_0800_1000,
+    0x4440_8080_0800_0400,
+    0x1002_0080_0400_0280,
+    0x4002_4400_300D_1248,
+    0x0010_0200_0040_8104,
+    0x0101_0082_0020_4200,
+    0x8020_0020_4000_5000,
+    0x4100_1000_8080_2000,
+    0x4008_006A_8010_0280,
+    0x1020_0800_8004_0080,
+    0x0004_0100_4002_0040,
+    0x0018_A124_0008_0290,
+    0x6140_0042_0000_8104,
+    0x4000_4000_2080_0090,
+    0x2020_0020_8080_4000,
+    0x0000_4082_0200_2010,
+    0x0080_1005_0100_0820,
+    0x0000_8004_0080_0800,
+    0x000A_2004_0801_4010,
+    0x0100_8002_0080_0100,
+    0xA008_0057_0200_008C,
+    0x0080_0040_6000_C010,
+    0x1040_1000_2800_2000,
+    0x0048_2001_0011_0040,
+    0x0068_4902_1003_0020,
+    0x1009_0800_0501_0010,
+    0x2142_0008_0401_0100,
+    0x1001_0801_1084_0002,
+    0x1801_0044_0082_0001,
+    0x0104_4020_8D02_0200,
+    0x0000_4000_2000_8080,
+    0x0200_2000_8010_0280,
+    0x0000_1000_2009_0100,
+    0x0204_0080_0802_0480,
+    0x8104_0100_4002_0040,
+    0x7800_0201_B008_0400,
+    0x0040_8000_5100_2880,
+    0x0050_1080_0100_2041,
+    0x208A_8011_0061_4003,
+    0x0006_0020_4208_9082,
+    0x0011_0900_0420_1001,
+    0x1002_0010_0420_0802,
+    0x0005_0002_0804_0001,
+    0x0002_0027_01AC_0822,
+    0x0000_1025_0184_004A,
+];
+const BISHOP_MAGICS: [u64; 64] = [
+    0xC0A0_0122_0604_0EA0,
+    0x8010_2282_0042_0001,
+    0x0110_0082_2040_0400,
+    0x0244_5C00_8010_6000,
+    0x0044_0420_0400_8100,
+    0x0880_9004_2040_8C05,
+    0x0201_0801_1008_0002,
+    0x0000_1080_9420_2000,
+    0x0000_0420_0204_0108,
+    0x0000_6230_2411_0042,
+    0x0086_1000_9481_1002,
+    0x0000_0445_0200_2080,
+    0x0100_4602_1140_0040,
+    0x0008_1090_0420_0004,
+    0x0202_3200_8484_4000,
+    0x8040_0424_2104_1009,
+    0x2010_10C0_5102_008C,
+    0x1020_8882_0802_4080,
+    0x0108_000C_8029_0200,
+    0x8048_0004_2042_5203,
+    0x0005_0000_9040_2000,
+    0x2080_4002_0110_4100,
+    0x8820_4201_1110_1000,
+    0x4AC0_3022_0882_1802,
+    0x0004_4000_1002_A840,
+    0x2002_2000_1004_1080,
+    0x1012_0802_0100_4400,
+    0x8440_0400_0241_0120,
+    0x1090_8200_8401_0400,
+    0x2084_8520_1202_1000,
+    0x1204_0062_C101_1003,
+    0x0200_8205_E109_0080,
+    0x088C_1028_0804_2080,
+    0x0802_1022_0090_4280,
+    0x8020_2090_0208_0020,
+    0x2200_0808_0006_0A00,
+    0x20C0_0040_1001_0100,
+    0x0802_0041_0082_1003,
+    0x0008_0244_0000_8080,
+    0x0000_8401_0200_8090,
+    0x0030_A404_2024_4007,
+    0x0A19_0842_1001_1282,
+    0x0004_0820_9001_9806,
+    0x6108_0042_0802_0080,
+    0x0081_2004_1011_0100,
+    0x1040_8107_0101_0208,
+    0x0282_0478_3201_2080,
+    0x0010_0200_9900_0020,
+    0x000E_0104_2240_0840,
+    0x1020_4208_B008_9090,
+    0x0810_0444_0C04_8000,
+    0x88C0_1800_8404_0001,
+    0x3100_0208_0304_0080,
+    0x8900_70A0_4121_0C00,
+    0x0020_2001_0101_0A09,
+    0x0004_1002_4041_0400,
+    0x0006_0044_0208_0200,
+    0x0801_0624_8404_2000,
+    0x0001_0002_D744_1004,
+    0x0810_0800_0020_8800,
+    0x0000_0208_0803_0411,
+    0x1450_0010_2001_4440,
+    0x0040_6008_1081_A288,
+    0x0044_0114_0410_8A00,
+];
+
+static SLIDER_TABLES: OnceLock<SliderTables> = OnceLock::new();
+
+// Struct to hold what is needed to look up the attacks of a slider on one square
+struct Magic {
+    mask: Bitboard, // Squares whose occupancy blocks the slider, without the edge squares
+    magic: u64,     // Multiplier mapping every blocker arrangement to its own index
+    shift: u32,     // Shift leaving as many index bits as the mask has squares
+    offset: usize,  // Start of the square's attacks in the shared attack table
+}
+
+// Struct to hold the magic lookups of rooks and bishops and the attacks they index
+struct SliderTables {
+    rook: Vec<Magic>,       // Lookup of every square for rooks
+    bishop: Vec<Magic>,     // Lookup of every square for bishops
+    attacks: Vec<Bitboard>, // Attacks of every square and blocker arrangement
+}
+
+/**
+ * Computes the squares a piece attacks from every square, for pieces moving a single step.
+ *
+ * @param steps - The file and rank offsets of the squares the piece attacks.
+ * @return The attacked squares from every square.
+ */
+const fn step_attacks(steps: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < steps.len() {
+            let x = (square % 8) as i8 + steps[i].0;
+            let y = (square / 8) as i8 + steps[i].1;
+            if x >= 0 && x < 8 && y >= 0 && y < 8 {
+                attacks[square] |= 1 << (y * 8 + x);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    attacks
+}
+
+/**
+ * Computes the squares a slider attacks by walking its rays until the first occupied square.
+ *
+ * @param square - The square of the slider.
+ * @param directions - The file and rank steps of the slider's rays.
+ * @param occupied - The occupied squares, which block the rays.
+ * @return The attacked squares, including the first occupied square of every ray.
+ */
+fn slide(square: usize, directions: &[(i8, i8)], occupied: Bitboard) -> Bitboard {
+    let mut attacks = 0;
+    for &(dx, dy) in directions {
+        let (mut x, mut y) = ((square % 8) as i8 + dx, (square / 8) as i8 + dy);
+        while (0..8).contains(&x) && (0..8).contains(&y) {
+            let bit = 1 << (y * 8 + x);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            x += dx;
+            y += dy;
+        }
+    }
+    attacks
+}
+
+/**
+ * Returns the squares whose occupancy can change the attacks of a slider.
+ *
+ * The last square of every ray is left out, as it is attacked whether it is occupied or not.
+ *
+ * @param square - The square of the slider.
+ * @param directions - The file and rank steps of the slider's rays.
+ * @return The relevant blocker squares.
+ */
+fn blocker_mask(square: usize, directions: &[(i8, i8)]) -> Bitboard {
+    let mut mask = 0;
+    for &(dx, dy) in directions {
+        let (mut x, mut y) = ((square % 8) as i8 + dx, (square / 8) as i8 + dy);
+        while (0..8).contains(&(x + dx)) && (0..8).contains(&(y + dy)) {
+            mask |= 1 << (y * 8 + x);
+            x += dx;
+            y += dy;
+        }
+    }
+    mask
+}
+
+/**
+ * Builds the magic lookup of a slider on one square and adds its attacks to the shared attack table.
+ *
+ * The magic maps every blocker arrangement to an index holding its attacks, and two arrangements with different
+ * attacks never share an index. Every slider attacks at least one square, so an empty entry marks an unused index.
+ *
+ * @param square - The square of the slider.
+ * @param directions - The file and rank steps of the slider's rays.
+ * @param magic - The magic multiplier of the square.
+ * @param attacks - The shared attack table, which the square's attacks are appended to.
+ * @return The magic lookup of the square.
+ */
+fn magic_lookup(
+    square: usize,
+    directions: &[(i8, i8)],
+    magic: u64,
+    attacks: &mut Vec<Bitboard>,
+) -> Magic {
+    let mask = blocker_mask(square, directions);
+    let bits = mask.count_ones();
+    let shift = 64 - bits;
+    let offset = attacks.len();
+    attacks.resize(offset + (1 << bits), 0);
+
+    // Enumerates every subset of the mask with the carry-rippler trick
+    let mut blockers: Bitboard = 0;
+    loop {
+        let slider_attacks = slide(square, directions, blockers);
+        let entry = &mut attacks[offset + (blockers.wrapping_mul(magic) >> shift) as usize];
+        assert!(
+            *entry == 0 || *entry == slider_attacks,
+            "the magic of square {} maps different attacks to one index",
+            square
+        );
+        *entry = slider_attacks;
+        blockers = blockers.wrapping_sub(mask) & mask;
+        if blockers == 0 {
+            break;
+        }
+    }
+    Magic {
+        mask,
+        magic,
+        shift,
+        offset,
+    }
+}
+
+/**
+ * Returns the magic lookup tables of rooks and bishops, building them on first use.
+ *
+ * The magics are precomputed, so building the tables only fills in the attacks of every blocker arrangement.
+ *
+ * @return The slider lookup tables.
+ */
+fn slider_tables() -> &'static SliderTables {
+    SLIDER_TABLES.get_or_init(|| {
+        let mut attacks = Vec::new();
+        let rook = (0..64)
+            .map(|square| magic_lookup(square, &ROOK_DIRECTIONS, ROOK_MAGICS[square], &mut attacks))
+            .collect();
+        let bishop = (0..64)
+            .map(|square| {
+                magic_lookup(
+                    square,
+                    &BISHOP_DIRECTIONS,
+                    BISHOP_MAGICS[square],
+                    &mut attacks,
+                )
+            })
+            .collect();
+        SliderTables {
+            rook,
+            bishop,
+            attacks,
+        }
+    })
+}
+
+/**
+ * Looks up the attacks of a slider in the magic tables.
+ *
+ * @param magic - The magic lookup of the slider's square.
+ * @param occupied - The occupied squares.
+ * @return The attacked squares.
+ */
+fn lookup(magic: &Magic, occupied: Bitboard) -> Bitboard {
+    let index = ((occupied & magic.mask).wrapping_mul(magic.magic) >> magic.shift) as usize;
+    slider_tables().attacks[magic.offset + index]
+}
+
+/**
+ * Returns the squares a knight attacks.
+ *
+ * @param square - The index of the knight's square.
+ * @return The attacked squares.
+ */
+pub fn knight_attacks(square: usize) -> Bitboard {
+    KNIGHT_ATTACKS[square]
+}
+
+/**
+ * Returns the squares a king attacks, not counting castling.
+ *
+ * @param square - The index of the king's square.
+ * @return The attacked squares.
+ */
+pub fn king_attacks(square: usize) -> Bitboard {
+    KING_ATTACKS[square]
+}
+
+/**
+ * Returns the squares a pawn attacks, which are the squares it captures on.
+ *
+ * @param color - The color of the pawn.
+ * @param square - The index of the pawn's square.
+ * @return The attacked squares.
+ */
+pub fn pawn_attacks(color: Color, square: usize) -> Bitboard {
+    PAWN_ATTACKS[color.to_idx() as usize][square]
+}
+
+/**
+ * Returns the squares a bishop attacks.
+ *
+ * @param square - The index of the bishop's square.
+ * @param occupied - The occupied squares, which block the bishop.
+ * @return The attacked squares, including the first occupied square on every diagonal.
+ */
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    lookup(&slider_tables().bishop[square], occupied)
+}
+
+/**
+ * Returns the squares a rook attacks.
+ *
+ * @param square - The index of the rook's square.
+ * @param occupied - The occupied squares, which block the rook.
+ * @return The attacked squares, including the first occupied square on every rank and file.
+ */
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    lookup(&slider_tables().rook[square], occupied)
+}
+
+/**
+ * Returns the squares a queen attacks.
+ *
+ * @param square - The index of the queen's square.
+ * @param occupied - The occupied squares, which block the queen.
+ * @return The attacked squares, including the first occupied square in every direction.
+ */
+pub fn queen_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+/**
+ * Returns the squares strictly between two squares on the same rank, file or diagonal.
+ *
+ * @param from - The index of the first square.
+ * @param to - The index of the second square.
+ * @return The squares between them, or an empty bitboard if the squares do not share a line.
+ */
+pub fn squares_between(from: usize, to: usize) -> Bitboard {
+    let (from_bit, to_bit): (Bitboard, Bitboard) = (1 << from, 1 << to);
+    if rook_attacks(from, 0) & to_bit != 0 {
+        rook_attacks(from, to_bit) & rook_attacks(to, from_bit)
+    } else if bishop_attacks(from, 0) & to_bit != 0 {
+        bishop_attacks(from, to_bit) & bishop_attacks(to, from_bit)
+    } else {
+        0
+    }
+}
+
+/**
+ * Returns the squares a piece attacks.
+ *
+ * @param kind - The kind of the piece.
+ * @param color - The color of the piece, which only matters for pawns.
+ * @param square - The index of the piece's square.
+ * @param occupied - The occupied squares, which block sliders.
+ * @return The attacked squares.
+ */
+pub fn piece_attacks(kind: PieceKind, color: Color, square: usize, occupied: Bitboard) -> Bitboard {
+    match kind {
+        PieceKind::Pawn => pawn_attacks(color, square),
+        PieceKind::Knight => knight_attacks(square),
+        PieceKind::Bishop => bishop_attacks(square, occupied),
+        PieceKind::Rook => rook_attacks(square, occupied),
+        PieceKind::Queen => queen_attacks(square, occupied),
+        PieceKind::King => king_attacks(square),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::attacks::{
+        bishop_attacks, blocker_mask, king_attacks, knight_attacks, pawn_attacks, queen_attacks,
+        rook_attacks, slide, squares_between, BISHOP_DIRECTIONS, ROOK_DIRECTIONS,
+    };
+    use crate::game::player::Color;
+
+    /**
+     * Advances an xorshift generator and returns its next number.
+     *
+     * @param state - The state of the generator.
+     * @return The next pseudo random number.
+     */
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    /**
+     * Tests the precomputed attacks of knights, kings and pawns, including on the edges of the board.
+     */
+    fn test_step_attacks() {
+        assert_eq!(knight_attacks(0), (1 << 10) | (1 << 17));
+        assert_eq!(knight_attacks(27).count_ones(), 8);
+        assert_eq!(king_attacks(7), (1 << 6) | (1 << 14) | (1 << 15));
+        assert_eq!(king_attacks(36).count_ones(), 8);
+        assert_eq!(pawn_attacks(Color::White, 12), (1 << 19) | (1 << 21));
+        assert_eq!(pawn_attacks(Color::Black, 52), (1 << 43) | (1 << 45));
+        assert_eq!(pawn_attacks(Color::White, 8), 1 << 17);
+        assert_eq!(pawn_attacks(Color::White, 60), 0);
+    }
+
+    #[test]
+    /**
+     * Tests that the magic lookups agree with walking the rays for random blocker arrangements on every square.
+     */
+    fn test_magic_attacks() {
+        let mut rng = 0x1234_5678_9ABC_DEF1;
+        for square in 0..64 {
+            assert!(blocker_mask(square, &ROOK_DIRECTIONS).count_ones() <= 12);
+            for _ in 0..64 {
+                let occupied = xorshift(&mut rng) & xorshift(&mut rng);
+                let rook = slide(square, &ROOK_DIRECTIONS, occupied);
+                let bishop = slide(square, &BISHOP_DIRECTIONS, occupied);
+                assert_eq!(rook_attacks(square, occupied), rook);
+                assert_eq!(bishop_attacks(square, occupied), bishop);
+                assert_eq!(queen_attacks(square, occupied), rook | bishop);
+            }
+        }
+
+        // A rook on a1 with blockers on a4 and d1
+        let occupied = (1 << 24) | (1 << 3);
+        assert_eq!(
+            rook_attacks(0, occupied),
+            (1 << 8) | (1 << 16) | (1 << 24) | (1 << 1) | (1 << 2) | (1 << 3)
+        );
+    }
+
+    #[test]
+    /**
+     * Tests the squares between two squares on a rank, a diagonal and no common line.
+     */
+    fn test_squares_between() {
+        assert_eq!(squares_between(0, 3), (1 << 1) | (1 << 2));
+        assert_eq!(squares_between(63, 27), (1 << 54) | (1 << 45) | (1 << 36));
+        assert_eq!(squares_between(4, 12), 0);
+        assert_eq!(squares_between(0, 17), 0);
+    }
+}
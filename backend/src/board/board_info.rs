@@ -1,23 +1,51 @@
+use crate::board::attacks::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks, piece_attacks, rook_attacks,
+    squares_between,
+};
 use crate::board::piece::{get_moves, Piece, PieceKind};
+use crate::board::zobrist::zobrist_key;
 use crate::board::{idx, Square};
-use crate::game::player::Color;
-use crate::rules::r#move::Move;
+use crate::eval::piece_value;
+use crate::game::player::{from_idx, Color};
+use crate::rules::move_list::PackedMove;
+use crate::rules::r#move::{CastleType, Move};
+use crate::rules::{castle_targets, pawn_move_targets};
 
 pub type Bitboard = u64;
 pub type Position = (u8, u8);
 
-#[derive(Clone)]
+// Piece kinds from the least to the most valuable, the order in which they join an exchange
+const EXCHANGE_ORDER: [PieceKind; 6] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+    PieceKind::King,
+];
+
+// Struct to hold the state a move destroys, so that the move can be undone exactly
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UndoState {
+    pub castling_rights: [bool; 4], // Castling rights before the move, in KQkq order
+    pub en_passant_target: Option<Position>, // En passant target before the move
+    pub halfmove_clock: u32,        // Halfmove clock before the move
+    pub captured: Square,           // Piece removed by the move, standing on its own square
+    pub castled_rook: Square,       // Rook moved by a castling move, as it was before castling
+    pub zobrist_key: u64,           // Zobrist key before the move
+}
+
+#[derive(Clone, Debug)]
 pub struct BoardInfo {
     pub squares: [Square; 64],           // Array of 64 Option<Piece> values
     pub piece_bitboards: [Bitboard; 12], // Array of 12 Bitboards, one for each piece type
     pub player_bitboards: [Bitboard; 2], // Array of 2 Bitboards, one for each player
     pub all_pieces_bitboard: Bitboard,   // Bitboard of all pieces
 
-    pub piece_capture_bitboards: [Bitboard; 12], // Array of 12 Bitboards, one for each piece that can be captured
-    pub color_capture_bitboards: [Bitboard; 2], // Array of 2 Bitboards, one for each player whose pieces can be captured
-
-    pub piece_move_bitboards: [Bitboard; 12], // Array of 12 Bitboards, one for each piece's moves
-    pub color_move_bitboards: [Bitboard; 2],  // Array of 2 Bitboards, one for each player's moves
+    pub square_attacks: [Bitboard; 64], // Squares attacked by the piece on each square, empty for empty squares
+    pub attack_bitboards: [Bitboard; 2], // Array of 2 Bitboards, one for the squares each player attacks
+    pub checkers: Bitboard,              // Enemy pieces giving check to the player to move
+    pub pinned: Bitboard,                // Pieces of the player to move pinned to their king
 
     pub white_king_pos: Position,
     pub black_king_pos: Position,
@@ -27,7 +55,13 @@ pub struct BoardInfo {
     pub black_can_castle_kingside: bool,
     pub black_can_castle_queenside: bool,
 
+    pub en_passant_target: Option<Position>, // Square a pawn may capture en passant onto
+    pub halfmove_clock: u32,                 // Plies since the last capture or pawn move
+    pub fullmove_number: u32,                // Starts at 1 and increments after Black's move
+    pub zobrist_key: u64, // Key identifying the position, kept up to date by every move
+
     pub move_history: Vec<Move>,
+    pub undo_stack: Vec<UndoState>, // One entry for every move in the move history
     pub captured_pieces: Vec<Piece>,
     pub white_psuedo_moves: Vec<Move>,
     pub black_psuedo_moves: Vec<Move>,
@@ -45,15 +79,19 @@ impl BoardInfo {
 
     * This function takes an array of `Square` values and initializes all the fields of the Chessboard struct.
 
-    * The piece_bitboards, player_bitboards, and color_capture_bitboards are initialized with zeros.
+    * The piece_bitboards, player_bitboards and all_pieces_bitboard are initialized with zeros.
 
-    * The all_pieces_bitboard, piece_capture_bitboards and color_move_bitboards are also initialized with zeros.
+    * The square_attacks, attack_bitboards, checkers and pinned bitboards are initialized with zeros as well.
 
-    * The king positions are initialized to (0, 0).
+    * The king positions are initialized to (0, 0) until the bitboards are built from the squares.
 
     * The can_castle_* fields are initialized to false.
 
-    * The move_history, captured_pieces, white_psuedo_moves, black_psuedo_moves, and valid_moves vectors are initialized as empty.
+    * There is no en passant target, the halfmove clock starts at 0 and the fullmove number at 1.
+
+    * The Zobrist key is computed from the squares.
+
+    * The move_history, undo_stack, captured_pieces, white_psuedo_moves, black_psuedo_moves, and valid_moves vectors are initialized as empty.
 
     *
 
@@ -63,17 +101,16 @@ impl BoardInfo {
 
     */
     pub fn new(squares: [Square; 64]) -> Self {
-        Self {
+        let mut board_info = Self {
             squares,
             piece_bitboards: [0; 12],
             player_bitboards: [0; 2],
             all_pieces_bitboard: 0,
 
-            piece_capture_bitboards: [0; 12],
-            color_capture_bitboards: [0; 2],
-
-            piece_move_bitboards: [0; 12],
-            color_move_bitboards: [0; 2],
+            square_attacks: [0; 64],
+            attack_bitboards: [0; 2],
+            checkers: 0,
+            pinned: 0,
 
             white_king_pos: (0, 0),
             black_king_pos: (0, 0),
@@ -83,14 +120,22 @@ impl BoardInfo {
             black_can_castle_kingside: false,
             black_can_castle_queenside: false,
 
+            en_passant_target: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            zobrist_key: 0,
+
             move_history: Vec::new(),
+            undo_stack: Vec::new(),
             captured_pieces: Vec::new(),
             white_psuedo_moves: Vec::new(),
             black_psuedo_moves: Vec::new(),
             valid_moves: Vec::new(),
 
             turn: 0,
-        }
+        };
+        board_info.zobrist_key = zobrist_key(&squares, &board_info);
+        board_info
     }
 
     // Getters and setters
@@ -232,109 +277,79 @@ impl BoardInfo {
     /**
      * Gets the capture bitboard for the specified piece and color.
      *
-     * This function retrieves the capture bitboard for the specified piece kind and player color
-     * from the Chessboard struct.
+     * This function returns the pieces of the specified kind and player color that stand on a square the enemy
+     * player attacks. It is computed from the attack bitboards when asked for.
      *
      * @param piece - The piece kind whose capture bitboard is to be retrieved.
      * @param color - The player color whose capture bitboard is to be retrieved.
      * @return The capture bitboard for the specified piece and color.
      */
     pub fn get_piece_capture_bitboard(&self, piece: PieceKind, color: Color) -> Bitboard {
-        self.piece_capture_bitboards[bb_piece_idx(piece, color)]
-    }
-
-    /**
-     * Sets the capture bitboard for a specific piece and player color.
-     *
-     * This function updates the capture bitboard for the specified piece and player color in the Chessboard struct.
-     *
-     * @param piece - The type of piece whose capture bitboard is to be set.
-     * @param color - The player color for which the capture bitboard is to be set.
-     * @param bitboard - The capture bitboard to set for the specified piece and player color.
-     */
-    pub fn set_piece_capture_bitboard(
-        &mut self,
-        piece: PieceKind,
-        color: Color,
-        bitboard: Bitboard,
-    ) {
-        self.piece_capture_bitboards[bb_piece_idx(piece, color)] = bitboard;
+        self.get_piece_bitboard(piece, color) & self.get_attack_bitboard(color.other())
     }
 
     /**
      * Retrieves the capture bitboard for the specified player color.
      *
-     * This function returns the capture bitboard associated with the specified player color
-     * from the Chessboard struct.
+     * This function returns the pieces of the specified player color that stand on a square the enemy player
+     * attacks. It is computed from the attack bitboards when asked for.
      *
      * @param color - The player color whose capture bitboard is to be retrieved.
      * @return The capture bitboard for the specified player color.
      */
     pub fn get_color_capture_bitboard(&self, color: Color) -> Bitboard {
-        self.color_capture_bitboards[bb_color_idx(color)]
-    }
-
-    /**
-     * Sets the capture bitboard for the given color.
-     *
-     * This function updates the capture bitboard for the specified color in the Chessboard struct.
-     *
-     * @param color - The color whose capture bitboard is to be set.
-     * @param bitboard - The bitboard to set for the specified color.
-     */
-    pub fn set_color_capture_bitboard(&mut self, color: Color, bitboard: Bitboard) {
-        self.color_capture_bitboards[bb_color_idx(color)] = bitboard;
+        self.get_player_bitboard(color) & self.get_attack_bitboard(color.other())
     }
 
     /**
      * Retrieves the bitboard containing possible moves for the specified piece of the given color.
      *
-     * This function returns the bitboard that represents the possible moves for a specific piece
-     * of the specified player color in the Chessboard struct.
+     * This function returns the target squares of the pseudo-legal moves of the pieces of the specified kind and
+     * player color. It is computed from the attacks of each piece when asked for, as pawns move differently from
+     * how they attack and castling depends on the enemy attacks.
      *
      * @param piece - The type of the piece for which the move bitboard is to be retrieved.
      * @param color - The color of the player whose piece move bitboard is to be retrieved.
      * @return The bitboard containing possible moves for the specified piece and color.
      */
     pub fn get_piece_move_bitboard(&self, piece: PieceKind, color: Color) -> Bitboard {
-        self.piece_move_bitboards[bb_piece_idx(piece, color)]
-    }
-
-    /**
-     * Sets the move bitboard for the specified piece and player color.
-     *
-     * This function updates the move bitboard for the specified piece and player color in the Chessboard struct.
-     *
-     * @param piece - The piece kind whose move bitboard is to be set.
-     * @param color - The player color whose move bitboard is to be set.
-     * @param bitboard - The move bitboard to set for the specified piece and player color.
-     */
-    pub fn set_piece_move_bitboard(&mut self, piece: PieceKind, color: Color, bitboard: Bitboard) {
-        self.piece_move_bitboards[bb_piece_idx(piece, color)] = bitboard;
+        if piece == PieceKind::Pawn {
+            return pawn_move_targets(self, color);
+        }
+        let own = self.get_player_bitboard(color);
+        let moves = bb_squares(self.get_piece_bitboard(piece, color)).fold(0, |moves, square| {
+            moves | self.square_attacks[square] & !own
+        });
+        if piece == PieceKind::King {
+            moves | castle_targets(self, color)
+        } else {
+            moves
+        }
     }
 
     /**
      * Retrieves the move bitboard for the specified player color.
      *
-     * This function returns the move bitboard associated with the specified player color from the Chessboard struct.
+     * This function returns the target squares of all pseudo-legal moves of the specified player color, computed
+     * when asked for.
      *
      * @param color - The player color for which to retrieve the move bitboard.
      * @return The move bitboard for the specified player color.
      */
     pub fn get_color_move_bitboard(&self, color: Color) -> Bitboard {
-        self.color_move_bitboards[bb_color_idx(color)]
+        EXCHANGE_ORDER.iter().fold(0, |moves, &kind| {
+            moves | self.get_piece_move_bitboard(kind, color)
+        })
     }
 
     /**
-     * Sets the move bitboard for the given color.
+     * Retrieves the squares attacked by the pieces of the specified player color.
      *
-     * This function updates the move bitboard for the specified player color in the Chessboard struct.
-     *
-     * @param color - The color whose move bitboard is to be set.
-     * @param bitboard - The move bitboard to set for the specified color.
+     * @param color - The player color whose attacked squares are to be retrieved.
+     * @return The bitboard of squares attacked by the specified player color.
      */
-    pub fn set_color_move_bitboard(&mut self, color: Color, bitboard: Bitboard) {
-        self.color_move_bitboards[bb_color_idx(color)] = bitboard;
+    pub fn get_attack_bitboard(&self, color: Color) -> Bitboard {
+        self.attack_bitboards[bb_color_idx(color)]
     }
 
     /**
@@ -415,8 +430,8 @@ impl BoardInfo {
      * Resets all bitboards in the Chessboard struct to their initial state.
      *
      * This function resets all bitboards in the Chessboard struct, including piece bitboards,
-     * player bitboards, all pieces bitboard, piece capture bitboards, color capture bitboards,
-     * piece move bitboards, and color move bitboards.
+     * player bitboards, all pieces bitboard, the attacks of every square, attack bitboards, checkers and
+     * pinned pieces.
      *
      * Note: After calling this function, all bitboards will be set to 0.
      */
@@ -425,11 +440,10 @@ impl BoardInfo {
         self.player_bitboards = [0; 2];
         self.all_pieces_bitboard = 0;
 
-        self.piece_capture_bitboards = [0; 12];
-        self.color_capture_bitboards = [0; 2];
-
-        self.piece_move_bitboards = [0; 12];
-        self.color_move_bitboards = [0; 2];
+        self.square_attacks = [0; 64];
+        self.attack_bitboards = [0; 2];
+        self.checkers = 0;
+        self.pinned = 0;
     }
 
     /**
@@ -449,36 +463,297 @@ impl BoardInfo {
     }
 
     /**
-     * Checks if the current player of the specified color is in check.
+     * Sets the position of the king for the specified player color.
+     *
+     * @param color - The player color whose king position is to be set.
+     * @param pos - The new position of the king.
+     */
+    pub fn set_king_pos(&mut self, color: Color, pos: Position) {
+        if color == Color::White {
+            self.white_king_pos = pos;
+        } else {
+            self.black_king_pos = pos;
+        }
+    }
+
+    /**
+     * Returns the castling rights of both players.
+     *
+     * @return The castling rights in KQkq order.
+     */
+    pub fn castling_rights(&self) -> [bool; 4] {
+        [
+            self.white_can_castle_kingside,
+            self.white_can_castle_queenside,
+            self.black_can_castle_kingside,
+            self.black_can_castle_queenside,
+        ]
+    }
+
+    /**
+     * Sets the castling rights of both players.
+     *
+     * @param rights - The castling rights in KQkq order.
+     */
+    pub fn set_castling_rights(&mut self, rights: [bool; 4]) {
+        self.white_can_castle_kingside = rights[0];
+        self.white_can_castle_queenside = rights[1];
+        self.black_can_castle_kingside = rights[2];
+        self.black_can_castle_queenside = rights[3];
+    }
+
+    /**
+     * Removes the castling right of the specified player color on the given side.
+     *
+     * @param color - The player color losing the castling right.
+     * @param castle_type - The side on which castling is no longer allowed.
+     */
+    pub fn remove_castling_right(&mut self, color: Color, castle_type: CastleType) {
+        match (color, castle_type) {
+            (Color::White, CastleType::KingSide) => self.white_can_castle_kingside = false,
+            (Color::White, CastleType::QueenSide) => self.white_can_castle_queenside = false,
+            (Color::Black, CastleType::KingSide) => self.black_can_castle_kingside = false,
+            (Color::Black, CastleType::QueenSide) => self.black_can_castle_queenside = false,
+        }
+    }
+
+    /**
+     * Checks if the king of the specified color is in check.
      *
-     * This function determines if the specified color's player is in check by checking if the
-     * king's position intersects with any of the enemy player's possible moves.
+     * This function looks the king up in the attack bitboard of the enemy player. Boards without a king of the
+     * given color are never in check.
      *
      * @param color - The player color to check for check.
      * @return true if the player is in check, false otherwise.
      */
     pub fn is_in_check(&self, color: Color) -> bool {
-        let enemy_color = color.other();
-        let king = self.king(color);
-        let enemy_moves = self.color_move_bitboards[bb_color_idx(enemy_color)];
-        (king & enemy_moves) != 0
+        self.king(color) & self.get_attack_bitboard(color.other()) != 0
     }
 
     /**
-     * Checks if the specified position on the chessboard is attacked by the given player color.
-     *
-     * This function determines if the position is targeted by any of the moves of the opposing player's pieces.
+     * Checks if the specified position on the chessboard is attacked by the opponent of the given player color.
      *
      * @param pos - The position to check for attack.
-     * @param color - The player color whose moves are to be considered.
-     * @return true if the position is attacked by the given player color, false otherwise.
+     * @param color - The player color whose opponent's pieces are considered.
+     * @return true if any piece of the opponent attacks the position, false otherwise.
      */
     pub fn is_attacked(&self, pos: Position, color: Color) -> bool {
-        let enemy_color = color.other();
-        let enemy_moves = self.color_move_bitboards[bb_color_idx(enemy_color)];
-        (pos_to_bb(pos) & enemy_moves) != 0
+        pos_to_bb(pos) & self.get_attack_bitboard(color.other()) != 0
+    }
+
+    /**
+     * Puts the given square on the chessboard and updates the piece bitboards for it.
+     *
+     * Whatever the bitboards held for the square before is cleared, so they stay correct even if the square was
+     * changed without them. Kings also update the stored king position of their color.
+     *
+     * @param position - The position of the square to be set.
+     * @param square - The square value to set at the specified position.
+     */
+    pub fn update_square(&mut self, position: Position, square: Square) {
+        let bit = pos_to_bb(position);
+        for bitboard in self
+            .piece_bitboards
+            .iter_mut()
+            .chain(self.player_bitboards.iter_mut())
+        {
+            *bitboard &= !bit;
+        }
+        self.all_pieces_bitboard &= !bit;
+        self.set_square(position, square);
+        if let Some(piece) = square {
+            update_bitboards(self, &piece, idx(position));
+        }
+    }
+
+    /**
+     * Recomputes the attacks of every square, the attack bitboards, checkers and pinned pieces from scratch.
+     *
+     * Everything is derived from the piece bitboards, so they have to be up to date first. Used when the board
+     * is built from its squares; moves use `update_attacks_around` instead.
+     */
+    pub fn update_attacks(&mut self) {
+        for square in 0..64 {
+            self.square_attacks[square] = self.attacks_of_square(square);
+        }
+        self.update_attack_bitboards();
+    }
+
+    /**
+     * Updates the attacks after the given squares changed, with the piece bitboards already up to date.
+     *
+     * Only the pieces on the changed squares and the rooks, bishops and queens whose lines reach one of them
+     * attack other squares than before; the attacks of every other piece are kept. The attack bitboards,
+     * checkers and pinned pieces are then rebuilt from the attacks of each square.
+     *
+     * @param changed - The positions of the squares whose piece was removed, placed or replaced.
+     */
+    pub fn update_attacks_around(&mut self, changed: &[Position]) {
+        let occupied = self.all_pieces_bitboard;
+        let straight = self.rook(Color::White)
+            | self.rook(Color::Black)
+            | self.queen(Color::White)
+            | self.queen(Color::Black);
+        let diagonal = self.bishop(Color::White)
+            | self.bishop(Color::Black)
+            | self.queen(Color::White)
+            | self.queen(Color::Black);
+        let mut stale = 0;
+        for &position in changed {
+            let square = idx(position);
+            stale |= bb(square)
+                | (rook_attacks(square, occupied) & straight)
+                | (bishop_attacks(square, occupied) & diagonal);
+        }
+        for square in bb_squares(stale) {
+            self.square_attacks[square] = self.attacks_of_square(square);
+        }
+        self.update_attack_bitboards();
+    }
+
+    /**
+     * Returns the squares attacked by the piece on a square, given the current occupancy.
+     *
+     * @param square - The index of the square.
+     * @return The bitboard of attacked squares, or an empty bitboard if the square is empty.
+     */
+    fn attacks_of_square(&self, square: usize) -> Bitboard {
+        self.squares[square].map_or(0, |piece| {
+            piece_attacks(piece.kind, piece.color, square, self.all_pieces_bitboard)
+        })
+    }
+
+    /**
+     * Rebuilds the attack bitboards of both players from the attacks of each square, and the checkers and pinned
+     * pieces of the player to move.
+     *
+     * A piece is pinned if it is the only piece between its own king and an enemy rook, bishop or queen that
+     * would attack the king without it.
+     */
+    fn update_attack_bitboards(&mut self) {
+        for color in [Color::White, Color::Black] {
+            self.attack_bitboards[bb_color_idx(color)] =
+                bb_squares(self.get_player_bitboard(color))
+                    .fold(0, |attacks, square| attacks | self.square_attacks[square]);
+        }
+
+        let occupied = self.all_pieces_bitboard;
+        let color = from_idx(self.turn);
+        let enemy = color.other();
+        self.checkers = 0;
+        self.pinned = 0;
+        if self.king(color) == 0 {
+            return;
+        }
+        let king_pos = self.king_pos(color);
+        let king_square = idx(king_pos);
+        self.checkers = self.attackers_to(king_pos, occupied) & self.get_player_bitboard(enemy);
+        let snipers = (rook_attacks(king_square, 0) & (self.rook(enemy) | self.queen(enemy)))
+            | (bishop_attacks(king_square, 0) & (self.bishop(enemy) | self.queen(enemy)));
+        for sniper in bb_squares(snipers) {
+            let blockers = squares_between(king_square, sniper) & occupied;
+            if blockers.count_ones() == 1 {
+                self.pinned |= blockers & self.get_player_bitboard(color);
+            }
+        }
+    }
+
+    /**
+     * Returns the squares attacked by the piece on the given position.
+     *
+     * Sliders are blocked by the pieces on the chessboard. Pawns attack the squares they capture on, not the
+     * squares they push to, and kings do not attack the squares they castle to. The attacks of every square are
+     * kept up to date by every move, so this is a lookup.
+     *
+     * @param position - The position of the attacking piece.
+     * @return The bitboard of attacked squares, or an empty bitboard if the square is empty.
+     */
+    pub fn attacks_from(&self, position: Position) -> Bitboard {
+        self.square_attacks[idx(position)]
+    }
+
+    /**
+     * Evaluates the material a move wins or loses once all captures on its target square are played out.
+     *
+     * Both players capture on the target square with their least valuable piece first and may stop capturing
+     * whenever continuing would lose material. Sliders hidden behind a piece that captured join the exchange
+     * (x-rays), and a king only captures if the square is no longer attacked. Pinned pieces are still counted.
+     *
+     * @param m - The move to evaluate, usually a capture.
+     * @return The material balance of the exchange in centipawns from the point of view of the moving player;
+     *         negative if the move loses material.
+     */
+    pub fn see(&self, m: PackedMove) -> i32 {
+        let Some(piece) = self.squares[m.from_square()].filter(|_| !m.is_castle()) else {
+            return 0;
+        };
+        let target = m.to_pos();
+        let mut occupied = self.all_pieces_bitboard & !(1 << m.from_square());
+        let captured = if m.is_en_passant() {
+            occupied &= !pos_to_bb((target.0, m.from_pos().1));
+            piece_value(PieceKind::Pawn)
+        } else {
+            self.get_square(target)
+                .map_or(0, |piece| piece_value(piece.kind))
+        };
+        let mut on_target = piece_value(m.promotion_kind().unwrap_or(piece.kind));
+        let mut gains = vec![captured + on_target - piece_value(piece.kind)];
+
+        let mut color = piece.color.other();
+        loop {
+            let attackers = self.attackers_to(target, occupied) & self.get_player_bitboard(color);
+            let Some((kind, attacker)) = EXCHANGE_ORDER.iter().find_map(|&kind| {
+                let bitboard = attackers & self.get_piece_bitboard(kind, color);
+                (bitboard != 0).then(|| (kind, bitboard & bitboard.wrapping_neg()))
+            }) else {
+                break;
+            };
+            occupied &= !attacker;
+            let defended =
+                self.attackers_to(target, occupied) & self.get_player_bitboard(color.other()) != 0;
+            if kind == PieceKind::King && defended {
+                break;
+            }
+            gains.push(on_target - gains[gains.len() - 1]);
+            on_target = piece_value(kind);
+            color = color.other();
+        }
+
+        // Every player only continues the exchange if that is better than stopping
+        for i in (1..gains.len()).rev() {
+            gains[i - 1] = -(-gains[i - 1]).max(gains[i]);
+        }
+        gains[0]
     }
 
+    /**
+     * Returns the pieces of both players that attack a square, given which squares are occupied.
+     *
+     * Sliders are blocked by the occupied squares only, so removing pieces from the occupancy reveals the
+     * sliders behind them.
+     *
+     * @param square - The attacked square.
+     * @param occupied - The bitboard of occupied squares; pieces not in it do not attack.
+     * @return The bitboard of the pieces attacking the square.
+     */
+    pub fn attackers_to(&self, square: Position, occupied: Bitboard) -> Bitboard {
+        let square = idx(square);
+        let diagonal = self.bishop(Color::White)
+            | self.bishop(Color::Black)
+            | self.queen(Color::White)
+            | self.queen(Color::Black);
+        let straight = self.rook(Color::White)
+            | self.rook(Color::Black)
+            | self.queen(Color::White)
+            | self.queen(Color::Black);
+        let attackers = (pawn_attacks(Color::Black, square) & self.pawn(Color::White))
+            | (pawn_attacks(Color::White, square) & self.pawn(Color::Black))
+            | (knight_attacks(square) & (self.knight(Color::White) | self.knight(Color::Black)))
+            | (king_attacks(square) & (self.king(Color::White) | self.king(Color::Black)))
+            | (bishop_attacks(square, occupied) & diagonal)
+            | (rook_attacks(square, occupied) & straight);
+        attackers & occupied
+    }
 }
 
 /**
@@ -550,12 +825,30 @@ pub fn pos_to_bb(pos: Position) -> Bitboard {
     1 << (x + y * 8)
 }
 
+/**
+ * Returns the square indices of the set bits of a bitboard.
+ *
+ * @param bitboard - The bitboard to read.
+ * @return An iterator over the square indices, from a1 to h8.
+ */
+pub fn bb_squares(bitboard: Bitboard) -> impl Iterator<Item = usize> {
+    let mut remaining = bitboard;
+    std::iter::from_fn(move || {
+        if remaining == 0 {
+            return None;
+        }
+        let square = remaining.trailing_zeros() as usize;
+        remaining &= remaining - 1;
+        Some(square)
+    })
+}
+
 /**
  * Updates the board information for the current board state.
  *
- * This function updates the board information for the current board state. It updates the bitboards
- * for the current player's pieces, the enemy player's pieces, and the current player's king position.
- * It also updates the current player's psuedo moves.
+ * This function updates the board information for the current board state. It copies the squares into
+ * the board information and rebuilds the bitboards for both players' pieces, including the attack and move
+ * bitboards. It then updates both players' psuedo moves.
  *
  * @param board_info - The board information to be updated.
  * @param squares - The squares of the chessboard.
@@ -566,31 +859,49 @@ pub fn update_board_info(board_info: BoardInfo, squares: [Option<Piece>; 64]) ->
     let mut white_psuedo_moves: Vec<Move> = Vec::new();
     let mut black_psuedo_moves: Vec<Move> = Vec::new();
 
-    for (i, square) in squares.iter().enumerate() {
-        if let Some(piece) = square {
-            update_bitboards(&mut board_info, piece, i);
+    update_piece_bitboards(&mut board_info, squares);
 
-            let moves = get_moves(&board_info, piece);
-            if piece.color == Color::White {
-                white_psuedo_moves.extend(moves);
-            } else {
-                black_psuedo_moves.extend(moves);
-            }
+    for piece in squares.iter().flatten() {
+        let moves = get_moves(&board_info, piece);
+        if piece.color == Color::White {
+            white_psuedo_moves.extend(moves);
+        } else {
+            black_psuedo_moves.extend(moves);
         }
     }
 
     board_info.white_psuedo_moves = white_psuedo_moves;
     board_info.black_psuedo_moves = black_psuedo_moves;
 
-    board_info.turn ^= 1;
     board_info
 }
 
+/**
+ * Copies the squares into the board information and rebuilds the piece bitboards from them.
+ *
+ * This is all the move generator and the evaluation need, so it is cheaper than `update_board_info` when the
+ * psuedo moves are not used. The king positions, attack bitboards, move bitboards, checkers and pinned pieces
+ * are updated as well.
+ *
+ * @param board_info - The board information to be updated.
+ * @param squares - The squares of the chessboard.
+ */
+pub fn update_piece_bitboards(board_info: &mut BoardInfo, squares: [Square; 64]) {
+    board_info.squares = squares;
+    board_info.reset_bitboards();
+    for (i, square) in squares.iter().enumerate() {
+        if let Some(piece) = square {
+            update_bitboards(board_info, piece, i);
+        }
+    }
+    board_info.update_attacks();
+}
+
 /**
  * Update the bitboards in the board information based on the given piece and position.
  *
  * This function updates the relevant bitboards in the BoardInfo struct based on the given piece
- * and its position on the chessboard.
+ * and its position on the chessboard. Kings also update the stored king position of their color.
  *
  * @param board_info - A mutable reference to the BoardInfo struct to be updated.
  * @param piece - The piece to be considered for updating the bitboards.
@@ -604,4 +915,7 @@ pub fn update_bitboards(board_info: &mut BoardInfo, piece: &Piece, position: usi
     board_info.piece_bitboards[bb_piece_idx(kind, color)] |= bitboard;
     board_info.player_bitboards[bb_color_idx(color)] |= bitboard;
     board_info.all_pieces_bitboard |= bitboard;
+    if kind == PieceKind::King {
+        board_info.set_king_pos(color, piece.position);
+    }
 }
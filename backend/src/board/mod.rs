@@ -1,18 +1,88 @@
-use crate::board::board_info::{update_board_info, BoardInfo};
+use crate::board::board_info::{update_board_info, update_piece_bitboards, BoardInfo, UndoState};
 use crate::board::piece::{to_char, Piece, PieceKind};
-use crate::game::player::Color;
+use crate::board::zobrist::{piece_key, state_key, zobrist_key};
 use crate::game::player::Color::{Black, White};
-use crate::rules::{generate_king_moves, generate_knight_moves, generate_pawn_moves, generate_sliding_move};
+use crate::game::player::{from_idx, Color};
+use crate::rules::move_list::MoveList;
+use crate::rules::{generate_legal_moves, generate_moves, is_legal_move};
 
 use crate::rules::r#move::{CastleType, Move, MoveType};
+use std::error::Error;
+use std::fmt::Display;
 
+pub mod attacks;
 pub mod board_info;
 pub mod piece;
+pub mod zobrist;
 
 pub type Position = (u8, u8);
 
 pub type Square = Option<Piece>;
 
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// Enum to represent the reasons a FEN string can be rejected
+#[derive(Clone, PartialEq, Debug)]
+pub enum FenError {
+    Empty,
+    BadRankCount(usize),
+    RankOverflow(u8),
+    RankTooShort(u8),
+    BadPieceLetter(char),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    TooManyFields,
+    MissingKing(Color),
+    TooManyKings(Color),
+    TooManyPieces(Color),
+    TooManyPawns(Color),
+    PawnOnBackRank(Position),
+    OpponentInCheck(Color),
+}
+
+impl Display for FenError {
+    /**
+     * Formats the FEN error as a human readable message.
+     *
+     * @param f - The formatter to write the message to.
+     * @return Result - Ok if the formatting is successful, Err otherwise.
+     */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::Empty => write!(f, "the FEN string is empty"),
+            FenError::BadRankCount(n) => write!(f, "expected 8 ranks but found {}", n),
+            FenError::RankOverflow(rank) => write!(f, "rank {} has more than 8 squares", rank),
+            FenError::RankTooShort(rank) => write!(f, "rank {} has fewer than 8 squares", rank),
+            FenError::BadPieceLetter(c) => write!(f, "'{}' is not a piece letter", c),
+            FenError::InvalidSideToMove(field) => write!(f, "invalid side to move '{}'", field),
+            FenError::InvalidCastling(field) => write!(f, "invalid castling rights '{}'", field),
+            FenError::InvalidEnPassant(field) => write!(f, "invalid en passant target '{}'", field),
+            FenError::InvalidHalfmoveClock(field) => {
+                write!(f, "invalid halfmove clock '{}'", field)
+            }
+            FenError::InvalidFullmoveNumber(field) => {
+                write!(f, "invalid fullmove number '{}'", field)
+            }
+            FenError::TooManyFields => write!(f, "the FEN string has more than 6 fields"),
+            FenError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            FenError::TooManyKings(color) => write!(f, "{:?} has more than one king", color),
+            FenError::TooManyPieces(color) => write!(f, "{:?} has more than 16 pieces", color),
+            FenError::TooManyPawns(color) => write!(f, "{:?} has more than 8 pawns", color),
+            FenError::PawnOnBackRank(pos) => {
+                write!(f, "pawn on the back rank at {}", algebraic_from_pos(*pos))
+            }
+            FenError::OpponentInCheck(color) => {
+                write!(f, "{:?} is in check but it is not their move", color)
+            }
+        }
+    }
+}
+
+impl Error for FenError {}
+
 #[derive(Clone)]
 pub struct Board {
     pub squares: [Square; 64],
@@ -53,17 +123,64 @@ impl Board {
      * Creates a new Chessboard instance based on the given FEN (Forsyth-Edwards Notation) string.
      *
      * This function creates a new Chessboard instance and initializes it with the pieces and their positions based on the provided FEN string.
+     * The side to move, castling rights, en passant target, halfmove clock and fullmove number are read from the remaining
+     * fields of the FEN string. Missing fields default to White to move, no castling rights, no en passant target and
+     * the counters of a new game. Malformed or impossible positions are rejected with a `FenError`.
      *
      * @param fen - The FEN string representing the initial state of the chessboard.
-     * @return A new Chessboard instance initialized with the pieces and positions from the FEN string.
+     * @return A new Chessboard instance initialized with the pieces and positions from the FEN string, or the reason the FEN string was rejected.
      */
-    pub fn new_from_fen(fen: &str) -> Self {
+    pub fn new_from_fen(fen: &str) -> Result<Self, FenError> {
         let mut board = Self::new();
+        let mut fields = fen.split_whitespace();
 
         // Generate squares from fen
-        board.squares = squares_from_fen(fen);
+        let placement = fields.next().ok_or(FenError::Empty)?;
+        board.squares = squares_from_fen(placement)?;
+        validate_squares(&board.squares)?;
+
+        let board_info = &mut board.board_info;
+        board_info.turn = match fields.next() {
+            None | Some("w") => 0,
+            Some("b") => 1,
+            Some(field) => return Err(FenError::InvalidSideToMove(field.to_string())),
+        };
+
+        // The side that just moved cannot have left its king in check
+        let waiting = from_idx(1 - board_info.turn);
+        let mut attack_info = BoardInfo::new(board.squares);
+        update_piece_bitboards(&mut attack_info, board.squares);
+        if attack_info.is_in_check(waiting) {
+            return Err(FenError::OpponentInCheck(waiting));
+        }
+
+        let castling = parse_castling_rights(fields.next().unwrap_or("-"), &board.squares)?;
+        board_info.set_castling_rights(castling);
+
+        board_info.en_passant_target = parse_en_passant(
+            fields.next().unwrap_or("-"),
+            board_info.turn,
+            &board.squares,
+        )?;
+
+        if let Some(field) = fields.next() {
+            board_info.halfmove_clock = field
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(field.to_string()))?;
+        }
+        if let Some(field) = fields.next() {
+            board_info.fullmove_number = match field.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenError::InvalidFullmoveNumber(field.to_string())),
+            };
+        }
+        if fields.next().is_some() {
+            return Err(FenError::TooManyFields);
+        }
 
-        board
+        board.reset_zobrist_key();
+        board.update();
+        Ok(board)
     }
 
     /**
@@ -75,20 +192,19 @@ impl Board {
      * @return - The newly created chessboard.
      */
     pub fn new_standard() -> Self {
-        let fen = "RNBQKBNR/PPPPPPPP/8/8/8/8/pppppppp/rnbqkbnr";
-        let mut board = Self::new_from_fen(fen);
-        board.update();
-        board
+        Self::new_from_fen(START_FEN).expect("the standard starting position is a valid FEN")
     }
 
     /**
      * Update the chessboard state.
      *
      * This function updates the chessboard state by calling `update_board_info` and updating the `board_info` field of
-     * the `Chessboard` struct based on the current state of `squares`.
+     * the `Chessboard` struct based on the current state of `squares`. The valid moves are set to the legal moves of
+     * the player to move.
      */
     pub fn update(&mut self) {
         self.board_info = update_board_info(self.board_info.clone(), self.squares);
+        self.board_info.valid_moves = self.get_legal_moves();
     }
 
     /**
@@ -107,37 +223,100 @@ impl Board {
      * Converts the chessboard representation to FEN notation.
      *
      * This function converts the current chessboard state to the Forsyth–Edwards Notation (FEN). It calls the `fen_from_squares`
-     * function which generates the FEN string based on the internal representation of the chessboard's squares.
+     * function which generates the piece placement based on the internal representation of the chessboard's squares, and
+     * appends the side to move, castling rights, en passant target, halfmove clock and fullmove number.
      *
      * @return The chessboard state represented in FEN notation.
      */
     pub fn to_fen(&self) -> String {
-        fen_from_squares(&self.squares)
+        let board_info = &self.board_info;
+        let side_to_move = if board_info.turn == 0 { "w" } else { "b" };
+        let en_passant = match board_info.en_passant_target {
+            Some(pos) => algebraic_from_pos(pos),
+            None => String::from("-"),
+        };
+        format!(
+            "{} {} {} {} {} {}",
+            fen_from_squares(&self.squares),
+            side_to_move,
+            fen_castling_rights(board_info),
+            en_passant,
+            board_info.halfmove_clock,
+            board_info.fullmove_number
+        )
+    }
+
+    /**
+     * Returns the Zobrist key identifying the position for repetition detection.
+     *
+     * Two positions are the same if the same pieces stand on the same squares, the same side is to move and
+     * the same castling rights and en passant captures are available.
+     *
+     * @return The key identifying the position.
+     */
+    pub fn position_key(&self) -> u64 {
+        self.board_info.zobrist_key
+    }
+
+    /**
+     * Recomputes the Zobrist key from scratch.
+     *
+     * Moves keep the key up to date, so this is only needed after changing the squares, castling rights, en passant
+     * target or turn directly.
+     */
+    pub fn reset_zobrist_key(&mut self) {
+        self.board_info.zobrist_key = zobrist_key(&self.squares, &self.board_info);
     }
 
     /**
      * Undoes the last move made on the chessboard.
      *
      * This function reverses the effects of the last move by restoring the previous state of the chessboard.
-     * It retrieves the last move from the move_history stack and updates the position of the moved piece, restores captured pieces (if any),
-     * and updates the squares on the chessboard accordingly.
+     * It retrieves the last move from the move_history stack and its saved state from the undo_stack. The moved
+     * piece is put back as it was before the move, which also reverts promotions, and the captured piece (if any)
+     * is restored to its own square, which covers en passant captures. A castled rook returns to its corner.
+     * The castling rights, en passant target, move counters, Zobrist key, king position and turn are restored as well.
+     *
+     * The bitboards and attacks are updated for the squares the move touched, the same way `make_move` updates
+     * them. Call `update` afterwards to rebuild the legal moves.
      */
     pub fn undo_move(&mut self) {
-        let m = self.board_info.move_history.pop();
-        if m.is_none() {
+        let Some(m) = self.board_info.move_history.pop() else {
             return;
+        };
+        let state = self
+            .board_info
+            .undo_stack
+            .pop()
+            .expect("every move in the move history has an undo state");
+
+        self.squares[idx(m.to)] = None;
+        if let Some(captured_piece) = state.captured {
+            self.board_info.captured_pieces.pop();
+            self.squares[idx(captured_piece.position)] = Some(captured_piece);
         }
-        let m = m.unwrap();
-        let piece = m.from_piece;
-        //piece.first_move = true;
-        let pos = piece.position;
-        if m.is_capture() {
-            let captured_piece = self.board_info.captured_pieces.pop().unwrap();
-            self.squares[idx(m.to)] = Some(captured_piece);
-        } else {
-            self.squares[idx(m.to)] = None;
+        if let Some(rook) = state.castled_rook {
+            let rook_x = if rook.position.0 == 0 { 3 } else { 5 };
+            self.squares[idx((rook_x, rook.position.1))] = None;
+            self.squares[idx(rook.position)] = Some(rook);
+        }
+        self.squares[idx(m.from)] = Some(m.from_piece);
+
+        let board_info = &mut self.board_info;
+        board_info.set_castling_rights(state.castling_rights);
+        board_info.en_passant_target = state.en_passant_target;
+        board_info.halfmove_clock = state.halfmove_clock;
+        if m.from_piece.kind == PieceKind::King {
+            board_info.set_king_pos(m.color, m.from);
         }
-        self.squares[idx(pos)] = Some(piece);
+        board_info.zobrist_key = state.zobrist_key;
+        board_info.turn ^= 1;
+        if board_info.turn == 1 {
+            board_info.fullmove_number -= 1;
+        }
+        self.debug_assert_zobrist_key();
+
+        self.update_touched_squares(&touched_squares(&m));
     }
 
     /**
@@ -145,10 +324,36 @@ impl Board {
      *
      * This function updates the chessboard state based on the given move. It updates the move history,
      * modifies the relevant pieces, captures pieces if necessary, and updates the position of the moved piece.
+     * Finally, it updates the en passant target, the move counters, the castling rights and the king position,
+     * and passes the turn to the other player. The Zobrist key is updated incrementally from the squares the move
+     * touches, and the state the move destroys is saved so `undo_move` can restore it. The squares and piece
+     * bitboards are updated where the move touched them, and so are the attacks of the pieces on or reaching those
+     * squares. Call `update` afterwards to rebuild the legal moves.
      *
      * @param m - The move to be made on the chessboard.
      */
     pub fn make_move(&mut self, m: Move) {
+        self.push_undo_state(&m);
+        let touched = touched_squares(&m);
+        let old_key = self.squares_key(&touched) ^ state_key(&self.squares, &self.board_info);
+        let resets_clock = m.from_piece.kind == PieceKind::Pawn
+            || m.is_capture()
+            || self.squares[idx(m.to)].is_some();
+        let board_info = &mut self.board_info;
+        board_info.en_passant_target = en_passant_target(&m);
+        board_info.halfmove_clock = if resets_clock {
+            0
+        } else {
+            board_info.halfmove_clock + 1
+        };
+        if board_info.turn == 1 {
+            board_info.fullmove_number += 1;
+        }
+        self.update_castling_rights(&m);
+        if m.from_piece.kind == PieceKind::King {
+            self.board_info.set_king_pos(m.color, m.to);
+        }
+
         self.board_info.move_history.push(m.clone());
         match m.move_type {
             MoveType::Castle(castle_type) => self.make_castle_move(m, castle_type),
@@ -159,6 +364,103 @@ impl Board {
             }
             _ => self.make_normal_move(m),
         }
+        self.board_info.turn ^= 1;
+        self.board_info.zobrist_key ^=
+            old_key ^ self.squares_key(&touched) ^ state_key(&self.squares, &self.board_info);
+        self.debug_assert_zobrist_key();
+        self.update_touched_squares(&touched);
+    }
+
+    /**
+     * Brings the board information up to date for the squares a move changed.
+     *
+     * The squares and piece bitboards are only updated where the move touched them. Only the attacks of the pieces
+     * on those squares and of the sliders whose lines reach them are recomputed, after which the attack bitboards,
+     * checkers and pinned pieces are rebuilt from the attacks of each square.
+     *
+     * @param touched - The squares changed by the move.
+     */
+    fn update_touched_squares(&mut self, touched: &[Position]) {
+        for &pos in touched {
+            self.board_info.update_square(pos, self.squares[idx(pos)]);
+        }
+        self.board_info.update_attacks_around(touched);
+    }
+
+    /**
+     * Combines the Zobrist keys of the pieces standing on the given squares.
+     *
+     * @param squares - The squares whose pieces to combine.
+     * @return The combined key of the pieces, or 0 if the squares are empty.
+     */
+    fn squares_key(&self, squares: &[Position]) -> u64 {
+        squares
+            .iter()
+            .filter_map(|&pos| self.squares[idx(pos)])
+            .fold(0, |key, piece| key ^ piece_key(&piece))
+    }
+
+    /**
+     * Checks in debug builds that the incrementally updated Zobrist key matches a recomputation from scratch.
+     */
+    fn debug_assert_zobrist_key(&self) {
+        debug_assert_eq!(
+            self.board_info.zobrist_key,
+            zobrist_key(&self.squares, &self.board_info),
+            "Zobrist key out of date after the moves {:?}",
+            self.board_info.move_history
+        );
+    }
+
+    /**
+     * Saves the state the given move is about to destroy on the undo stack.
+     *
+     * @param m - The move about to be made on the chessboard.
+     */
+    fn push_undo_state(&mut self, m: &Move) {
+        let captured = match m.move_type {
+            MoveType::EnPassant => self.squares[idx((m.to.0, m.from.1))],
+            _ => self.squares[idx(m.to)],
+        };
+        let castled_rook = match m.move_type {
+            MoveType::Castle(CastleType::KingSide) => self.squares[idx((7, m.from.1))],
+            MoveType::Castle(CastleType::QueenSide) => self.squares[idx((0, m.from.1))],
+            _ => None,
+        };
+        let board_info = &mut self.board_info;
+        board_info.undo_stack.push(UndoState {
+            castling_rights: board_info.castling_rights(),
+            en_passant_target: board_info.en_passant_target,
+            halfmove_clock: board_info.halfmove_clock,
+            captured,
+            castled_rook,
+            zobrist_key: board_info.zobrist_key,
+        });
+    }
+
+    /**
+     * Removes the castling rights lost by the given move.
+     *
+     * A king move loses both castling rights of its color. A move from or onto a corner square loses the
+     * castling right of the rook that started on that corner, whether it moved away or was captured.
+     *
+     * @param m - The move about to be made on the chessboard.
+     */
+    fn update_castling_rights(&mut self, m: &Move) {
+        let board_info = &mut self.board_info;
+        if m.from_piece.kind == PieceKind::King {
+            board_info.remove_castling_right(m.color, CastleType::KingSide);
+            board_info.remove_castling_right(m.color, CastleType::QueenSide);
+        }
+        for pos in [m.from, m.to] {
+            match pos {
+                (0, 0) => board_info.remove_castling_right(White, CastleType::QueenSide),
+                (7, 0) => board_info.remove_castling_right(White, CastleType::KingSide),
+                (0, 7) => board_info.remove_castling_right(Black, CastleType::QueenSide),
+                (7, 7) => board_info.remove_castling_right(Black, CastleType::KingSide),
+                _ => (),
+            }
+        }
     }
 
     /**
@@ -289,39 +591,44 @@ impl Board {
     }
 
     pub fn get_current_moves(&self) -> Vec<Move> {
-        let mut moves = Vec::new();
-        let mut piece: Piece;
-        let turn: Color = if self.board_info.turn == 0 {
-            White
-        } else {
-            Black
-        };
-        for p in self.squares.iter().flatten() {
-            piece = p.clone();
-            if piece.color == turn {
-                match piece.kind {
-                    PieceKind::Pawn => {
-                        moves.append(&mut generate_pawn_moves(self.board_info.clone(), piece));
-                    }
-                    PieceKind::Knight => {
-                        moves.append(&mut generate_knight_moves(self.board_info.clone(), piece));
-                    }
-                    PieceKind::Bishop => {
-                        moves.append(&mut generate_sliding_move(self.board_info.clone(), piece));
-                    }
-                    PieceKind::Rook => {
-                        moves.append(&mut generate_sliding_move(self.board_info.clone(), piece));
-                    }
-                    PieceKind::Queen => {
-                        moves.append(&mut generate_sliding_move(self.board_info.clone(), piece));
-                    }
-                    PieceKind::King => {
-                        moves.append(&mut generate_king_moves(self.board_info.clone(), piece));
-                    }
-                }
-            }
-        }
-        moves
+        let mut moves = MoveList::new();
+        generate_moves(&self.board_info, from_idx(self.board_info.turn), &mut moves);
+        moves.to_moves(&self.board_info)
+    }
+
+    /**
+     * Retrieves the legal moves for the player to move.
+     *
+     * This function filters the pseudo-legal moves returned by `get_current_moves` down to the moves that do not
+     * leave the player's own king in check. Pinned pieces, double checks, en passant captures that expose the king
+     * and castling out of or through check are all handled.
+     *
+     * @return A vector containing the legal moves for the player to move.
+     */
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        generate_legal_moves(&self.board_info, from_idx(self.board_info.turn), &mut moves);
+        moves.to_moves(&self.board_info)
+    }
+
+    /**
+     * Brings the squares, piece bitboards and attack bitboards of the board information up to date with the squares.
+     *
+     * Unlike `update`, this does not generate any moves. `make_move` and `undo_move` keep the bitboards up to date
+     * on their own, so this is only needed after the squares were changed directly.
+     */
+    pub fn update_bitboards(&mut self) {
+        update_piece_bitboards(&mut self.board_info, self.squares);
+    }
+
+    /**
+     * Checks if a pseudo-legal move is legal on this chessboard.
+     *
+     * @param m - The pseudo-legal move to check.
+     * @return true if the move does not leave the moving side's king in check, false otherwise.
+     */
+    pub fn is_legal_move(&self, m: &Move) -> bool {
+        is_legal_move(&self.board_info, m)
     }
 }
 
@@ -339,81 +646,191 @@ pub fn idx(pos: Position) -> usize {
 }
 
 /**
- * Generates the FEN string from the given squares.
+ * Generates the squares from the given FEN piece placement.
  *
- * This function generates the Forsyth–Edwards Notation (FEN) string from the given squares.
+ * This function generates the squares from the piece placement field of a Forsyth–Edwards Notation (FEN) string.
+ * The first rank in the FEN string is rank 8, which is stored at y = 7. Every rank must describe exactly eight squares.
  *
- * @param squares - The squares to generate the FEN string from.
- * @return The FEN string representing the given squares.
+ * @param fen - The FEN piece placement to generate the squares from.
+ * @return The squares represented by the given FEN piece placement, or the reason the placement was rejected.
  */
-pub fn squares_from_fen(fen: &str) -> [Square; 64] {
+pub fn squares_from_fen(fen: &str) -> Result<[Square; 64], FenError> {
     let mut squares = [None; 64];
-    let mut pos: Position = (0, 0);
-    for c in fen.chars() {
-        match c {
-            '/' => {
-                pos.0 = 0;
-                pos.1 += 1;
+    let ranks: Vec<&str> = fen.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::BadRankCount(ranks.len()));
+    }
+
+    for (i, rank) in ranks.iter().enumerate() {
+        let y = 7 - i as u8;
+        let mut x = 0u8;
+        for c in rank.chars() {
+            if x >= 8 {
+                return Err(FenError::RankOverflow(y + 1));
             }
-            '1'..='8' => {
-                let n = c as u8 - b'0';
-                for _ in 0..n {
-                    squares[idx(pos)] = None;
-                    pos.0 += 1;
+            match c {
+                '1'..='8' => {
+                    x += c as u8 - b'0';
+                    if x > 8 {
+                        return Err(FenError::RankOverflow(y + 1));
+                    }
+                }
+                _ => {
+                    let (kind, color) = piece_from_char(c).ok_or(FenError::BadPieceLetter(c))?;
+                    squares[idx((x, y))] = Some(Piece::new(kind, (x, y), color));
+                    x += 1;
                 }
             }
-            'p' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Pawn, pos, Color::Black));
-                pos.0 += 1;
-            }
-            'r' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Rook, pos, Color::Black));
-                pos.0 += 1;
-            }
-            'n' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Knight, pos, Color::Black));
-                pos.0 += 1;
-            }
-            'b' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Bishop, pos, Color::Black));
-                pos.0 += 1;
-            }
-            'q' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Queen, pos, Color::Black));
-                pos.0 += 1;
-            }
-            'k' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::King, pos, Color::Black));
-                pos.0 += 1;
-            }
-            'P' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Pawn, pos, Color::White));
-                pos.0 += 1;
-            }
-            'R' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Rook, pos, Color::White));
-                pos.0 += 1;
-            }
-            'N' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Knight, pos, Color::White));
-                pos.0 += 1;
-            }
-            'B' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Bishop, pos, Color::White));
-                pos.0 += 1;
-            }
-            'Q' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::Queen, pos, Color::White));
-                pos.0 += 1;
-            }
-            'K' => {
-                squares[idx(pos)] = Some(Piece::new(PieceKind::King, pos, Color::White));
-                pos.0 += 1;
-            }
-            _ => (),
+        }
+        if x < 8 {
+            return Err(FenError::RankTooShort(y + 1));
         }
     }
-    squares
+    Ok(squares)
+}
+
+/**
+ * Converts a FEN piece letter to its piece kind and color.
+ *
+ * Uppercase letters are white pieces and lowercase letters are black pieces.
+ *
+ * @param c - The piece letter.
+ * @return The piece kind and color, or None if the letter is not a piece.
+ */
+pub fn piece_from_char(c: char) -> Option<(PieceKind, Color)> {
+    let kind = match c.to_ascii_lowercase() {
+        'p' => PieceKind::Pawn,
+        'r' => PieceKind::Rook,
+        'n' => PieceKind::Knight,
+        'b' => PieceKind::Bishop,
+        'q' => PieceKind::Queen,
+        'k' => PieceKind::King,
+        _ => return None,
+    };
+    let color = if c.is_ascii_uppercase() { White } else { Black };
+    Some((kind, color))
+}
+
+/**
+ * Checks that the squares describe a position that can be played from.
+ *
+ * Each side must have exactly one king, at most 16 pieces and at most 8 pawns, and no pawn may stand on the first
+ * or last rank.
+ *
+ * @param squares - The squares to check.
+ * @return Ok if the squares are playable, or the reason they were rejected.
+ */
+pub fn validate_squares(squares: &[Square; 64]) -> Result<(), FenError> {
+    for color in [White, Black] {
+        let kings = squares
+            .iter()
+            .flatten()
+            .filter(|p| p.kind == PieceKind::King && p.color == color)
+            .count();
+        match kings {
+            0 => return Err(FenError::MissingKing(color)),
+            1 => (),
+            _ => return Err(FenError::TooManyKings(color)),
+        }
+
+        let pieces = squares.iter().flatten().filter(|p| p.color == color);
+        if pieces.clone().count() > 16 {
+            return Err(FenError::TooManyPieces(color));
+        }
+        if pieces.filter(|p| p.kind == PieceKind::Pawn).count() > 8 {
+            return Err(FenError::TooManyPawns(color));
+        }
+    }
+
+    let back_rank_pawn = squares
+        .iter()
+        .flatten()
+        .find(|p| p.kind == PieceKind::Pawn && (p.position.1 == 0 || p.position.1 == 7));
+    match back_rank_pawn {
+        Some(pawn) => Err(FenError::PawnOnBackRank(pawn.position)),
+        None => Ok(()),
+    }
+}
+
+/**
+ * Parses the castling rights field of a FEN string.
+ *
+ * The field is either "-" or a combination of "K", "Q", "k" and "q" without repeats. Every right requires the
+ * king and the rook of that side to stand on their original squares.
+ *
+ * @param field - The castling rights field.
+ * @param squares - The squares of the position the rights belong to.
+ * @return The white kingside, white queenside, black kingside and black queenside rights, or the reason the field was rejected.
+ */
+pub fn parse_castling_rights(field: &str, squares: &[Square; 64]) -> Result<[bool; 4], FenError> {
+    let invalid = || FenError::InvalidCastling(field.to_string());
+    let mut rights = [false; 4];
+    if field == "-" {
+        return Ok(rights);
+    }
+    if field.is_empty() {
+        return Err(invalid());
+    }
+
+    for c in field.chars() {
+        let (i, color, rook_x) = match c {
+            'K' => (0, White, 7),
+            'Q' => (1, White, 0),
+            'k' => (2, Black, 7),
+            'q' => (3, Black, 0),
+            _ => return Err(invalid()),
+        };
+        if rights[i] {
+            return Err(invalid());
+        }
+
+        let back_rank = if color == White { 0 } else { 7 };
+        let in_place = |pos: Position, kind: PieceKind| matches!(squares[idx(pos)], Some(p) if p.kind == kind && p.color == color);
+        if !in_place((4, back_rank), PieceKind::King)
+            || !in_place((rook_x, back_rank), PieceKind::Rook)
+        {
+            return Err(invalid());
+        }
+        rights[i] = true;
+    }
+    Ok(rights)
+}
+
+/**
+ * Parses the en passant target field of a FEN string.
+ *
+ * The field is either "-" or the square passed over by the pawn that just made a double push. That square must
+ * be empty, lie on rank 3 when Black is to move or rank 6 when White is to move, and have the pushed pawn in front of it.
+ *
+ * @param field - The en passant target field.
+ * @param turn - The side to move, 0 for White and 1 for Black.
+ * @param squares - The squares of the position the target belongs to.
+ * @return The en passant target square, or the reason the field was rejected.
+ */
+pub fn parse_en_passant(
+    field: &str,
+    turn: u8,
+    squares: &[Square; 64],
+) -> Result<Option<Position>, FenError> {
+    if field == "-" {
+        return Ok(None);
+    }
+    let invalid = || FenError::InvalidEnPassant(field.to_string());
+    let target = pos_from_algebraic(field).ok_or_else(invalid)?;
+
+    let (target_rank, pawn_rank, pushed_color) = if turn == 0 {
+        (5, 4, Black)
+    } else {
+        (2, 3, White)
+    };
+    let pushed_pawn = matches!(
+        squares[idx((target.0, pawn_rank))],
+        Some(p) if p.kind == PieceKind::Pawn && p.color == pushed_color
+    );
+    if target.1 != target_rank || squares[idx(target)].is_some() || !pushed_pawn {
+        return Err(invalid());
+    }
+    Ok(Some(target))
 }
 
 /**
@@ -427,7 +844,7 @@ pub fn squares_from_fen(fen: &str) -> [Square; 64] {
 pub fn fen_from_squares(squares: &[Square; 64]) -> String {
     let mut fen = String::new();
     let mut empty_squares = 0;
-    for y in 0..8 {
+    for y in (0..8).rev() {
         for x in 0..8 {
             let pos = (x, y);
             let idx = idx(pos);
@@ -445,27 +862,120 @@ pub fn fen_from_squares(squares: &[Square; 64]) -> String {
             fen.push_str(&empty_squares.to_string());
             empty_squares = 0;
         }
-        if y < 7 {
+        if y > 0 {
             fen.push('/');
         }
     }
     fen
 }
 
+/**
+ * Generates the castling rights field of a FEN string.
+ *
+ * @param board_info - The board information holding the castling rights.
+ * @return The castling rights in FEN notation, or "-" if neither side can castle.
+ */
+pub fn fen_castling_rights(board_info: &BoardInfo) -> String {
+    let mut rights = String::new();
+    if board_info.white_can_castle_kingside {
+        rights.push('K');
+    }
+    if board_info.white_can_castle_queenside {
+        rights.push('Q');
+    }
+    if board_info.black_can_castle_kingside {
+        rights.push('k');
+    }
+    if board_info.black_can_castle_queenside {
+        rights.push('q');
+    }
+    if rights.is_empty() {
+        rights.push('-');
+    }
+    rights
+}
+
+/**
+ * Returns the en passant target square created by the given move.
+ *
+ * A double pawn push creates an en passant target on the square the pawn passed over; every other move clears it.
+ *
+ * @param m - The move that was made.
+ * @return The en passant target square, or None if the move was not a double pawn push.
+ */
+pub fn en_passant_target(m: &Move) -> Option<Position> {
+    if m.move_type == MoveType::DoublePawnPush {
+        Some((m.to.0, (m.from.1 + m.to.1) / 2))
+    } else {
+        None
+    }
+}
+
+/**
+ * Returns the squares whose contents the given move changes.
+ *
+ * @param m - The move about to be made.
+ * @return The squares the move leaves, enters, captures on or moves a castled rook between.
+ */
+fn touched_squares(m: &Move) -> Vec<Position> {
+    let mut squares = vec![m.from, m.to];
+    match m.move_type {
+        MoveType::EnPassant => squares.push((m.to.0, m.from.1)),
+        MoveType::Castle(CastleType::KingSide) => squares.extend([(7, m.from.1), (5, m.from.1)]),
+        MoveType::Castle(CastleType::QueenSide) => squares.extend([(0, m.from.1), (3, m.from.1)]),
+        _ => (),
+    }
+    squares
+}
+
+/**
+ * Converts a square name in algebraic notation to a position.
+ *
+ * @param name - The square name, such as "e4".
+ * @return The position of the square, or None if the name is not a valid square.
+ */
+pub fn pos_from_algebraic(name: &str) -> Option<Position> {
+    let bytes = name.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+    let pos = (bytes[0].wrapping_sub(b'a'), bytes[1].wrapping_sub(b'1'));
+    if in_bounds(pos) {
+        Some(pos)
+    } else {
+        None
+    }
+}
+
+/**
+ * Converts a position to its square name in algebraic notation.
+ *
+ * @param pos - The position to convert.
+ * @return The square name, such as "e4".
+ */
+pub fn algebraic_from_pos(pos: Position) -> String {
+    format!("{}{}", (b'a' + pos.0) as char, (b'1' + pos.1) as char)
+}
+
 /**
  * Checks if the board state represented by the FEN string is in check for the given player color.
  *
- * This function creates a new Board instance, initializes it with the squares parsed from the FEN string,
- * and then checks if the specified player color is in check on the board.
+ * This function parses the squares from the FEN piece placement, builds their bitboards and then checks if the king
+ * of the specified player color is attacked. Piece placements that cannot be parsed are never in check.
  *
  * @param fen - The FEN string representing the board state.
  * @param color - The player color to check for check.
  * @return true if the player is in check, false otherwise.
  */
 pub fn is_fen_in_check(fen: &str, color: Color) -> bool {
-    let mut board = Board::new();
-    board.squares = squares_from_fen(fen);
-    board.board_info.is_in_check(color)
+    match squares_from_fen(fen) {
+        Ok(squares) => {
+            let mut board_info = BoardInfo::new(squares);
+            update_piece_bitboards(&mut board_info, squares);
+            board_info.is_in_check(color)
+        }
+        Err(_) => false,
+    }
 }
 
 pub fn display_board(board: &Board) {
@@ -500,12 +1010,14 @@ pub fn in_bounds(pos: Position) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use crate::board::board_info::{bb_color_idx, bb_piece_idx, pos_to_bb, update_board_info};
     use crate::board::piece::PieceKind;
     use crate::board::piece::PieceKind::King;
     use crate::board::PieceKind::{Bishop, Pawn, Queen};
-    use crate::board::{display_board, Board};
+    use crate::board::{display_board, Board, FenError, START_FEN};
     use crate::game::player::Color;
     use crate::game::player::Color::{Black, White};
+    use crate::rules::move_list::PackedMove;
     use crate::rules::r#move::CastleType::{KingSide, QueenSide};
     use crate::rules::r#move::Move;
     use crate::rules::r#move::MoveType::{
@@ -536,7 +1048,7 @@ mod tests {
      * Finally, it asserts that the piece at position (0, 0) on the board is a Rook.
      */
     pub fn test_fen_board_creation() {
-        let board = Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
+        let board = Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
         display_board(&board);
 
         assert_eq!(board.get_piece((0, 0)).unwrap().kind, PieceKind::Rook);
@@ -621,7 +1133,12 @@ mod tests {
      * @param color - The color of the player making the move.
      */
     fn test_promotion_capture(board: &mut Board, from: (u8, u8), to: (u8, u8), color: Color) {
-        let m = Move::new(board.get_piece(from).unwrap(), to, PromotionCapture(Queen), color);
+        let m = Move::new(
+            board.get_piece(from).unwrap(),
+            to,
+            PromotionCapture(Queen),
+            color,
+        );
         board.make_move(m);
     }
 
@@ -1026,4 +1543,566 @@ mod tests {
         // Assert that the piece at the 'from' position is a pawn
         assert_eq!(board.get_piece(from).unwrap().kind, Pawn);
     }
+
+    #[test]
+    /**
+     * Tests that complete FEN strings survive a round trip through the board.
+     *
+     * This function loads several FEN strings covering both sides to move, partial castling rights,
+     * en passant targets and non-zero move counters, and asserts that `to_fen` reproduces each of them.
+     */
+    pub fn test_fen_round_trip() {
+        let fens = [
+            START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "r3k2r/8/8/8/3pP3/8/8/R3K2R b Kq e3 0 30",
+            "8/8/8/8/8/8/8/4K2k b - - 12 47",
+        ];
+        for fen in fens {
+            let board = Board::new_from_fen(fen).unwrap();
+            display_board(&board);
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    /**
+     * Tests that every FEN field is loaded into the board information.
+     */
+    pub fn test_fen_fields() {
+        let board = Board::new_from_fen("r3k2r/8/8/8/3pP3/8/8/R3K2R b Kq e3 5 30").unwrap();
+        let board_info = &board.board_info;
+
+        assert_eq!(board.get_piece((4, 0)).unwrap().kind, King);
+        assert_eq!(board.get_piece((4, 0)).unwrap().color, White);
+        assert_eq!(board.get_piece((4, 7)).unwrap().color, Black);
+        assert_eq!(board_info.turn, 1);
+        assert!(board_info.white_can_castle_kingside);
+        assert!(!board_info.white_can_castle_queenside);
+        assert!(!board_info.black_can_castle_kingside);
+        assert!(board_info.black_can_castle_queenside);
+        assert_eq!(board_info.en_passant_target, Some((4, 2)));
+        assert_eq!(board_info.halfmove_clock, 5);
+        assert_eq!(board_info.fullmove_number, 30);
+
+        // The black pawn on d4 can capture en passant onto e3
+        assert!(board_info
+            .valid_moves
+            .iter()
+            .any(|m| m.move_type == EnPassant && m.to == (4, 2)));
+    }
+
+    #[test]
+    /**
+     * Tests that making moves keeps the FEN fields up to date.
+     *
+     * This function plays 1. e4 c5 2. Nf3 from the standard position and asserts the complete FEN
+     * after each move, including the en passant target and the move counters.
+     */
+    pub fn test_fen_after_moves() {
+        let mut board = Board::new_standard();
+        let expected = [
+            (
+                (4, 1),
+                (4, 3),
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            ),
+            (
+                (2, 6),
+                (2, 4),
+                "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            ),
+            (
+                (6, 0),
+                (5, 2),
+                "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            ),
+        ];
+        for (from, to, fen) in expected {
+            let m = board
+                .get_legal_moves()
+                .into_iter()
+                .find(|m| m.from == from && m.to == to)
+                .unwrap();
+            board.make_move(m);
+            board.update();
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    /**
+     * Tests that malformed and impossible FEN strings are rejected with the matching error.
+     */
+    pub fn test_fen_errors() {
+        let cases = [
+            ("", FenError::Empty),
+            ("8/8/8/8/8/8/8 w - - 0 1", FenError::BadRankCount(7)),
+            ("4k3/8/8/8/8/8/8/8/4K3 w - - 0 1", FenError::BadRankCount(9)),
+            ("4k3/8/8/8/8/8/8/4K4 w - - 0 1", FenError::RankOverflow(1)),
+            (
+                "4k3/8/8/ppppppppp/8/8/8/4K3 w - - 0 1",
+                FenError::RankOverflow(5),
+            ),
+            ("4k3/8/8/8/7/8/8/4K3 w - - 0 1", FenError::RankTooShort(4)),
+            (
+                "4k3/8/8/8/3X4/8/8/4K3 w - - 0 1",
+                FenError::BadPieceLetter('X'),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+                FenError::InvalidSideToMove(String::from("x")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w KA - 0 1",
+                FenError::InvalidCastling(String::from("KA")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+                FenError::InvalidCastling(String::from("KK")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+                FenError::InvalidCastling(String::from("K")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e9 0 1",
+                FenError::InvalidEnPassant(String::from("e9")),
+            ),
+            (
+                "4k3/8/8/8/4P3/8/8/4K3 b - e6 0 1",
+                FenError::InvalidEnPassant(String::from("e6")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+                FenError::InvalidEnPassant(String::from("e3")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+                FenError::InvalidHalfmoveClock(String::from("x")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                FenError::InvalidFullmoveNumber(String::from("0")),
+            ),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1 x", FenError::TooManyFields),
+            ("8/8/8/8/8/8/8/4K3 w - - 0 1", FenError::MissingKing(Black)),
+            ("4k3/8/8/8/8/8/8/8 w - - 0 1", FenError::MissingKing(White)),
+            (
+                "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+                FenError::TooManyKings(White),
+            ),
+            (
+                "4k2P/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::PawnOnBackRank((7, 7)),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/p3K3 w - - 0 1",
+                FenError::PawnOnBackRank((0, 0)),
+            ),
+            (
+                "QQQQQQQk/Q6Q/Q6Q/Q6Q/Q6Q/Q6Q/Q6Q/KQQQQQQQ w - - 0 1",
+                FenError::TooManyPieces(White),
+            ),
+            (
+                "4k3/8/8/8/8/N7/PPPPPPPP/RNBQKBNR w - - 0 1",
+                FenError::TooManyPieces(White),
+            ),
+            (
+                "4k3/p7/8/8/8/1P6/PPPPPPPP/4K3 w - - 0 1",
+                FenError::TooManyPawns(White),
+            ),
+            (
+                "4k3/pppppppp/p7/8/8/8/8/4K3 b - - 0 1",
+                FenError::TooManyPawns(Black),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2r b - - 0 1",
+                FenError::OpponentInCheck(White),
+            ),
+            (
+                "4k3/3P4/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::OpponentInCheck(Black),
+            ),
+        ];
+        for (fen, expected) in cases {
+            match Board::new_from_fen(fen) {
+                Ok(_) => panic!("{} should have been rejected", fen),
+                Err(error) => {
+                    println!("{}: {}", fen, error);
+                    assert_eq!(error, expected);
+                }
+            }
+        }
+
+        // Valid en passant targets and castling rights are still accepted
+        assert!(Board::new_from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").is_ok());
+        assert!(Board::new_from_fen("r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1").is_ok());
+    }
+
+    /**
+     * Plays the legal move from one square to another on the board.
+     *
+     * @param board - The board to play the move on.
+     * @param from - The square the piece moves from.
+     * @param to - The square the piece moves to.
+     */
+    fn play(board: &mut Board, from: (u8, u8), to: (u8, u8)) {
+        let m = board
+            .get_legal_moves()
+            .into_iter()
+            .find(|m| m.from == from && m.to == to)
+            .unwrap();
+        board.make_move(m);
+        board.update();
+    }
+
+    #[test]
+    /**
+     * Tests that castling rights are lost by king moves, rook moves and rook captures, and that the king
+     * positions follow the kings.
+     */
+    pub fn test_castling_rights() {
+        let mut board = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.board_info.king_pos(White), (4, 0));
+        assert_eq!(board.board_info.king_pos(Black), (4, 7));
+
+        // Capturing the rook on h8 removes Black's kingside right and White's kingside right
+        play(&mut board, (7, 0), (7, 7));
+        assert_eq!(board.to_fen(), "r3k2R/8/8/8/8/8/8/R3K3 b Qq - 0 1");
+
+        play(&mut board, (4, 7), (3, 6));
+        assert_eq!(board.to_fen(), "r6R/3k4/8/8/8/8/8/R3K3 w Q - 1 2");
+        assert_eq!(board.board_info.king_pos(Black), (3, 6));
+
+        play(&mut board, (0, 0), (1, 0));
+        assert_eq!(board.to_fen(), "r6R/3k4/8/8/8/8/8/1R2K3 b - - 2 2");
+
+        let mut board = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        play(&mut board, (4, 0), (6, 0));
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+        assert_eq!(board.board_info.king_pos(White), (6, 0));
+    }
+
+    #[test]
+    /**
+     * Tests that castling is not generated out of check or through an attacked square.
+     */
+    pub fn test_castling_through_attack() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/5r2/R3K2R w KQ - 0 1").unwrap();
+        let castles: Vec<Move> = board
+            .get_current_moves()
+            .into_iter()
+            .filter(|m| matches!(m.move_type, Castle(_)))
+            .collect();
+        assert_eq!(castles.len(), 1);
+        assert_eq!(castles[0].move_type, Castle(QueenSide));
+
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1").unwrap();
+        assert!(board
+            .get_current_moves()
+            .iter()
+            .all(|m| !matches!(m.move_type, Castle(_))));
+
+        // The rook passes over b1, so an attack on b1 does not prevent queenside castling
+        let board = Board::new_from_fen("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        assert!(board
+            .get_legal_moves()
+            .iter()
+            .any(|m| m.move_type == Castle(QueenSide)));
+    }
+
+    /**
+     * Describes every part of the board state that a move and its undo could change.
+     *
+     * @param board - The board to describe.
+     * @return A description of the squares and the board information.
+     */
+    fn snapshot(board: &Board) -> String {
+        format!("{:?} {:?}", board.squares, board.board_info)
+    }
+
+    /**
+     * Makes and undoes every move in the perft tree of the given depth.
+     *
+     * After each undo the board must be exactly as it was before the move was made. Leaf moves are undone
+     * without calling `update` in between, so the undo alone has to restore the state.
+     *
+     * @param board - The board to walk the perft tree from.
+     * @param depth - The remaining depth of the perft tree, at least 1.
+     * @return The number of leaf positions visited.
+     */
+    fn round_trip(board: &mut Board, depth: usize) -> usize {
+        let before = snapshot(board);
+        let mut nodes = 0;
+        for m in board.board_info.valid_moves.clone() {
+            board.make_move(m.clone());
+            if depth > 1 {
+                board.update();
+                nodes += round_trip(board, depth - 1);
+                board.undo_move();
+                board.update();
+            } else {
+                nodes += 1;
+                board.undo_move();
+            }
+            assert_eq!(snapshot(board), before, "undoing {:?}", m);
+        }
+        nodes
+    }
+
+    #[test]
+    /**
+     * Tests that undoing a move restores the board exactly, for every move in a few perft trees.
+     *
+     * The positions cover castling on both sides, castling rights lost to rook captures, en passant
+     * captures and promotions with and without a capture.
+     */
+    pub fn test_make_undo_round_trip() {
+        let positions = [
+            (START_FEN, 3, 8902),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                2,
+                2039,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3, 2812),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                2,
+                264,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                2,
+                1486,
+            ),
+        ];
+        for (fen, depth, expected) in positions {
+            let mut board = Board::new_from_fen(fen).unwrap();
+            assert_eq!(round_trip(&mut board, depth), expected, "{}", fen);
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
+    /**
+     * Plays the given moves in UCI notation on the board.
+     *
+     * @param board - The board to play the moves on.
+     * @param moves - The moves to play, such as "e2e4".
+     */
+    fn play_uci(board: &mut Board, moves: &[&str]) {
+        for uci in moves {
+            let m = Move::from_uci(board, uci).unwrap();
+            board.make_move(m);
+            board.update();
+        }
+    }
+
+    #[test]
+    /**
+     * Tests that the Zobrist key identifies positions by pieces, player to move, castling rights and en passant
+     * file, however the position was reached.
+     */
+    pub fn test_zobrist_keys() {
+        let start = Board::new_standard();
+        let mut board = Board::new_standard();
+        play_uci(&mut board, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_eq!(board.board_info.zobrist_key, start.board_info.zobrist_key);
+
+        // The en passant file only counts when a pawn could capture en passant
+        play_uci(&mut board, &["e2e4"]);
+        let fen_key = |fen: &str| Board::new_from_fen(fen).unwrap().board_info.zobrist_key;
+        let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq";
+        assert_eq!(
+            board.board_info.zobrist_key,
+            fen_key(&format!("{} e3 0 1", after_e4))
+        );
+        assert_eq!(
+            board.board_info.zobrist_key,
+            fen_key(&format!("{} - 0 1", after_e4))
+        );
+        play_uci(&mut board, &["a7a6", "e4e5", "d7d5"]);
+        let with_capture = "rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq";
+        assert_eq!(
+            board.board_info.zobrist_key,
+            fen_key(&format!("{} d6 0 3", with_capture))
+        );
+        assert_ne!(
+            board.board_info.zobrist_key,
+            fen_key(&format!("{} - 0 3", with_capture))
+        );
+
+        assert_ne!(
+            fen_key("4k3/8/8/8/8/8/8/R3K2R w KQ -"),
+            fen_key("4k3/8/8/8/8/8/8/R3K2R w K -")
+        );
+        assert_ne!(
+            fen_key("4k3/8/8/8/8/8/8/R3K2R w - -"),
+            fen_key("4k3/8/8/8/8/8/8/R3K2R b - -")
+        );
+
+        board.undo_move();
+        board.undo_move();
+        board.update();
+        assert_eq!(
+            board.board_info.zobrist_key,
+            fen_key("rnbqkbnr/1ppppppp/p7/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2")
+        );
+    }
+
+    #[test]
+    /**
+     * Tests the static exchange evaluation of captures and quiet moves, including x-ray attackers and kings
+     * that cannot recapture on a defended square.
+     */
+    pub fn test_static_exchange_evaluation() {
+        let see = |fen: &str, uci: &str| {
+            let board = Board::new_from_fen(fen).unwrap();
+            board
+                .board_info
+                .see(PackedMove::from(&Move::from_uci(&board, uci).unwrap()))
+        };
+        assert_eq!(see("4k3/8/8/3r4/8/4N3/8/4K3 w - - 0 1", "e3d5"), 500);
+        assert_eq!(see("4k3/8/2p5/3q4/4P3/8/8/4K3 w - - 0 1", "e4d5"), 800);
+        assert_eq!(see("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "d2d5"), -800);
+        assert_eq!(see("4k3/8/8/2p5/8/8/8/3QK3 w - - 0 1", "d1d4"), -900);
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+
+        // The rook behind the first one recaptures through it
+        assert_eq!(see("3r2k1/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), 100);
+        assert_eq!(see("3r2k1/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), -400);
+
+        // The king only recaptures if the square is not defended, also by an x-ray
+        assert_eq!(see("4k3/8/8/8/8/3r4/3P4/4K3 b - - 0 1", "d3d2"), -400);
+        assert_eq!(see("3rk3/8/8/8/8/3r4/3P4/4K3 b - - 0 1", "d3d2"), 100);
+    }
+
+    #[test]
+    /**
+     * Tests the attack bitboards, capture bitboards, checkers and pinned pieces of a position with a check and a pin.
+     */
+    pub fn test_attack_bitboards() {
+        let board = Board::new_from_fen("4k3/8/8/8/1b6/8/3P4/4K2r w - - 0 1").unwrap();
+        let info = &board.board_info;
+        assert_eq!(info.checkers, pos_to_bb((7, 0)));
+        assert_eq!(info.pinned, pos_to_bb((3, 1)));
+        assert!(info.is_in_check(White));
+        assert!(!info.is_in_check(Black));
+        assert!(info.is_attacked((5, 0), White));
+        assert!(!info.is_attacked((4, 1), White));
+        assert_eq!(
+            info.get_color_capture_bitboard(White),
+            pos_to_bb((3, 1)) | pos_to_bb((4, 0))
+        );
+        assert_eq!(
+            info.get_piece_capture_bitboard(Pawn, White),
+            pos_to_bb((3, 1))
+        );
+        assert_eq!(info.get_color_capture_bitboard(Black), 0);
+
+        // The pinned pawn cannot move and the king cannot step back along the rook's line
+        let mut moves: Vec<String> = info.valid_moves.iter().map(|m| m.to_uci()).collect();
+        moves.sort();
+        assert_eq!(moves, ["e1e2", "e1f2"]);
+    }
+
+    #[test]
+    /**
+     * Tests that making and undoing moves two plies deep keeps the bitboards, the attacks of every square, the
+     * attack bitboards, checkers and pinned pieces equal to rebuilding them from the squares.
+     */
+    pub fn test_incremental_attack_bitboards() {
+        fn assert_up_to_date(board: &Board) {
+            let mut rebuilt = board.clone();
+            rebuilt.update_bitboards();
+            let (info, expected) = (&board.board_info, &rebuilt.board_info);
+            assert_eq!(info.squares, expected.squares);
+            assert_eq!(info.piece_bitboards, expected.piece_bitboards);
+            assert_eq!(info.player_bitboards, expected.player_bitboards);
+            assert_eq!(info.all_pieces_bitboard, expected.all_pieces_bitboard);
+            assert_eq!(info.square_attacks, expected.square_attacks);
+            assert_eq!(info.attack_bitboards, expected.attack_bitboards);
+            assert_eq!(info.checkers, expected.checkers);
+            assert_eq!(info.pinned, expected.pinned);
+        }
+
+        fn walk(board: &mut Board, depth: u32) {
+            assert_up_to_date(board);
+            if depth == 0 {
+                return;
+            }
+            for m in board.get_legal_moves() {
+                board.make_move(m);
+                walk(board, depth - 1);
+                board.undo_move();
+                assert_up_to_date(board);
+            }
+        }
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/1p6/8/2pP4/8/5b2/8/R3K2R w KQkq c6 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            walk(&mut Board::new_from_fen(fen).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    /**
+     * Tests that the move bitboards, computed when asked for, hold the target squares of the pseudo moves
+     * `update_board_info` generates, also after making and undoing moves.
+     */
+    pub fn test_incremental_move_bitboards() {
+        let assert_up_to_date = |board: &Board| {
+            let fresh = update_board_info(board.board_info.clone(), board.squares);
+            let mut piece_moves = [0; 12];
+            let mut color_moves = [0; 2];
+            for m in fresh
+                .white_psuedo_moves
+                .iter()
+                .chain(&fresh.black_psuedo_moves)
+            {
+                piece_moves[bb_piece_idx(m.from_piece.kind, m.color)] |= pos_to_bb(m.to);
+                color_moves[bb_color_idx(m.color)] |= pos_to_bb(m.to);
+            }
+            let info = &board.board_info;
+            for color in [White, Black] {
+                for kind in [
+                    King,
+                    Pawn,
+                    PieceKind::Knight,
+                    Bishop,
+                    PieceKind::Rook,
+                    Queen,
+                ] {
+                    assert_eq!(
+                        info.get_piece_move_bitboard(kind, color),
+                        piece_moves[bb_piece_idx(kind, color)]
+                    );
+                }
+                assert_eq!(
+                    info.get_color_move_bitboard(color),
+                    color_moves[bb_color_idx(color)]
+                );
+            }
+        };
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/1p6/8/2pP4/8/5b2/8/R3K2R w KQkq c6 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            let mut board = Board::new_from_fen(fen).unwrap();
+            assert_up_to_date(&board);
+            for m in board.board_info.valid_moves.clone() {
+                board.make_move(m);
+                assert_up_to_date(&board);
+                board.undo_move();
+                assert_up_to_date(&board);
+            }
+        }
+    }
 }
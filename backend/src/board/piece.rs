@@ -135,8 +135,7 @@ impl Piece {
  * @return The character representation of the piece.
  */
 pub fn to_char(piece: Piece) -> char {
-    let mut char: String = String::new();
-    char = match piece.kind {
+    let mut char: String = match piece.kind {
         PieceKind::Pawn => "P",
         PieceKind::Rook => "R",
         PieceKind::Knight => "N",
@@ -177,8 +176,7 @@ impl Display for Piece {
      * @return Result - Ok if the formatting is successful, Err otherwise.
      */
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut char: String = String::new();
-        char = match self.kind {
+        let mut char: String = match self.kind {
             PieceKind::Pawn => "P",
             PieceKind::Rook => "R",
             PieceKind::Knight => "N",
@@ -209,11 +207,11 @@ impl Display for Piece {
 pub fn get_moves(board_info: &BoardInfo, p: &Piece) -> Vec<Move> {
     let piece = *p;
     match piece.kind {
-        PieceKind::Pawn => generate_pawn_moves(board_info.clone(), piece),
-        PieceKind::Rook => generate_sliding_move(board_info.clone(), piece),
-        PieceKind::Knight => generate_knight_moves(board_info.clone(), piece),
-        PieceKind::Bishop => generate_sliding_move(board_info.clone(), piece),
-        PieceKind::Queen => generate_sliding_move(board_info.clone(), piece),
-        PieceKind::King => generate_king_moves(board_info.clone(), piece),
+        PieceKind::Pawn => generate_pawn_moves(board_info, piece),
+        PieceKind::Rook => generate_sliding_move(board_info, piece),
+        PieceKind::Knight => generate_knight_moves(board_info, piece),
+        PieceKind::Bishop => generate_sliding_move(board_info, piece),
+        PieceKind::Queen => generate_sliding_move(board_info, piece),
+        PieceKind::King => generate_king_moves(board_info, piece),
     }
 }
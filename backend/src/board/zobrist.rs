@@ -0,0 +1,138 @@
+use crate::board::board_info::{bb_piece_idx, BoardInfo};
+use crate::board::piece::{Piece, PieceKind};
+use crate::board::{idx, Square};
+use crate::game::player::Color;
+
+// Struct to hold the random numbers a Zobrist key is made of
+struct Keys {
+    pieces: [[u64; 64]; 12], // One number per piece kind and color on every square
+    castling: [u64; 4],      // One number per castling right, in KQkq order
+    en_passant: [u64; 8],    // One number per en passant file
+    black_to_move: u64,      // Number for Black being the player to move
+}
+
+const KEYS: Keys = generate_keys();
+
+/**
+ * Advances the SplitMix64 generator and returns its next number.
+ *
+ * @param state - The state of the generator.
+ * @return The next pseudo random number.
+ */
+const fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/**
+ * Generates the random numbers of the Zobrist keys from a fixed seed, so keys are the same in every run.
+ *
+ * @return The random numbers of the Zobrist keys.
+ */
+const fn generate_keys() -> Keys {
+    let mut state = 0x5EED_C4E5_5B0A_2D00;
+    let mut keys = Keys {
+        pieces: [[0; 64]; 12],
+        castling: [0; 4],
+        en_passant: [0; 8],
+        black_to_move: 0,
+    };
+    let mut piece = 0;
+    while piece < 12 {
+        let mut square = 0;
+        while square < 64 {
+            keys.pieces[piece][square] = split_mix(&mut state);
+            square += 1;
+        }
+        piece += 1;
+    }
+    let mut i = 0;
+    while i < 8 {
+        if i < 4 {
+            keys.castling[i] = split_mix(&mut state);
+        }
+        keys.en_passant[i] = split_mix(&mut state);
+        i += 1;
+    }
+    keys.black_to_move = split_mix(&mut state);
+    keys
+}
+
+/**
+ * Returns the Zobrist key of a piece standing on its square.
+ *
+ * @param piece - The piece, whose position is the square it stands on.
+ * @return The number identifying the piece on its square.
+ */
+pub fn piece_key(piece: &Piece) -> u64 {
+    KEYS.pieces[bb_piece_idx(piece.kind, piece.color)][idx(piece.position)]
+}
+
+/**
+ * Returns the Zobrist key of everything but the pieces: the castling rights, the en passant file and the player
+ * to move.
+ *
+ * The en passant file only counts if a pawn of the player to move stands next to the pawn that just made a double
+ * push, so positions where no en passant capture is possible get the same key.
+ *
+ * @param squares - The squares of the chessboard.
+ * @param board_info - The board information holding the castling rights, en passant target and turn.
+ * @return The number identifying the state of the position.
+ */
+pub fn state_key(squares: &[Square; 64], board_info: &BoardInfo) -> u64 {
+    let mut key = 0;
+    for (right, castling_key) in board_info.castling_rights().iter().zip(KEYS.castling) {
+        if *right {
+            key ^= castling_key;
+        }
+    }
+    if let Some(file) = en_passant_file(squares, board_info) {
+        key ^= KEYS.en_passant[file as usize];
+    }
+    if board_info.turn == 1 {
+        key ^= KEYS.black_to_move;
+    }
+    key
+}
+
+/**
+ * Computes the Zobrist key of a position from scratch.
+ *
+ * @param squares - The squares of the chessboard.
+ * @param board_info - The board information holding the castling rights, en passant target and turn.
+ * @return The 64-bit key identifying the position.
+ */
+pub fn zobrist_key(squares: &[Square; 64], board_info: &BoardInfo) -> u64 {
+    squares
+        .iter()
+        .flatten()
+        .fold(state_key(squares, board_info), |key, piece| {
+            key ^ piece_key(piece)
+        })
+}
+
+/**
+ * Returns the file of the en passant target if the player to move has a pawn that could capture en passant.
+ *
+ * @param squares - The squares of the chessboard.
+ * @param board_info - The board information holding the en passant target and turn.
+ * @return The file of the en passant target, or None if no pawn can capture en passant.
+ */
+fn en_passant_file(squares: &[Square; 64], board_info: &BoardInfo) -> Option<u8> {
+    let (x, y) = board_info.en_passant_target?;
+    let (color, pawn_y) = if board_info.turn == 0 {
+        (Color::White, y.checked_sub(1)?)
+    } else {
+        (Color::Black, y + 1)
+    };
+    let can_capture = [x.checked_sub(1), Some(x + 1)]
+        .into_iter()
+        .flatten()
+        .filter(|&pawn_x| pawn_x < 8 && pawn_y < 8)
+        .filter_map(|pawn_x| squares[idx((pawn_x, pawn_y))])
+        .any(|piece| piece.kind == PieceKind::Pawn && piece.color == color);
+    can_capture.then_some(x)
+}
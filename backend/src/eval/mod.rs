@@ -0,0 +1,609 @@
+use crate::board::board_info::{pos_to_bb, Bitboard, BoardInfo};
+use crate::board::piece::PieceKind;
+use crate::board::{Board, Position};
+use crate::game::player::{from_idx, Color};
+use std::fmt::Display;
+use std::ops::{AddAssign, Sub};
+
+pub const MAX_PHASE: i32 = 24; // Game phase with all pieces on the board
+
+const FILE_A: Bitboard = 0x0101_0101_0101_0101; // Bitboard of the a-file
+
+const DOUBLED_PAWN: Score = Score(-10, -20); // Penalty for every extra pawn on a file
+const ISOLATED_PAWN: Score = Score(-15, -10); // Penalty for a pawn without friendly pawns on adjacent files
+const PASSED_PAWN: [i32; 8] = [0, 5, 10, 20, 35, 60, 100, 0]; // Endgame bonus for a passed pawn by relative rank
+const BISHOP_PAIR: Score = Score(30, 50); // Bonus for having two or more bishops
+const MOBILITY: Score = Score(3, 3); // Bonus for every square a player's pieces can move to
+const PAWN_SHIELD: [i32; 3] = [-15, 12, 6]; // Bonus for no, a close or a far shield pawn in front of the king
+const KING_ZONE_ATTACK: i32 = -8; // Penalty for every square next to the king the opponent can move to
+
+// Piece-square tables from White's point of view, with the eighth rank in the first row
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    20,  20,  20,  20,  20,  20,  20,  20,
+    10,  10,  10,  10,  10,  10,  10,  10,
+    10,  10,  10,  10,  10,  10,  10,  10,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+// Struct to hold a middlegame and an endgame score, blended by the game phase
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Score(i32, i32);
+
+impl Score {
+    /**
+     * Blends the middlegame and endgame scores according to the game phase.
+     *
+     * @param phase - The game phase, from `MAX_PHASE` in the opening down to 0 in a pawn endgame.
+     * @return The blended score.
+     */
+    fn taper(self, phase: i32) -> i32 {
+        (self.0 * phase + self.1 * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+
+    /**
+     * Multiplies both scores by the given factor.
+     *
+     * @param factor - The factor to multiply by.
+     * @return The multiplied score.
+     */
+    fn times(self, factor: i32) -> Self {
+        Score(self.0 * factor, self.1 * factor)
+    }
+}
+
+impl AddAssign for Score {
+    /**
+     * Adds another score to both phases of this score.
+     *
+     * @param other - The score to add.
+     */
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+        self.1 += other.1;
+    }
+}
+
+impl Sub for Score {
+    type Output = Self;
+
+    /**
+     * Subtracts another score from both phases of this score.
+     *
+     * @param other - The score to subtract.
+     * @return The difference of the scores.
+     */
+    fn sub(self, other: Self) -> Self {
+        Score(self.0 - other.0, self.1 - other.1)
+    }
+}
+
+// Struct to hold the terms of an evaluation, each from the point of view of the player to move
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Evaluation {
+    pub material: i32,       // Value of the pieces on the board
+    pub piece_squares: i32,  // Bonuses and penalties for where the pieces stand
+    pub pawn_structure: i32, // Doubled, isolated and passed pawns
+    pub king_safety: i32,    // Pawn shield and attacks next to the king
+    pub mobility: i32,       // Squares the pieces can move to
+    pub bishop_pair: i32,    // Bonus for keeping both bishops
+    pub phase: i32,          // Game phase the terms were blended with
+}
+
+impl Evaluation {
+    /**
+     * Returns the sum of all terms of the evaluation.
+     *
+     * @return The score in centipawns, positive if the player to move is better.
+     */
+    pub fn total(&self) -> i32 {
+        self.material
+            + self.piece_squares
+            + self.pawn_structure
+            + self.king_safety
+            + self.mobility
+            + self.bishop_pair
+    }
+}
+
+impl Display for Evaluation {
+    /**
+     * Formats the evaluation as a table of its terms followed by the total.
+     *
+     * @param f - The formatter to write the table to.
+     * @return Result - Ok if the formatting is successful, Err otherwise.
+     */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "material        {:>6}", self.material)?;
+        writeln!(f, "piece squares   {:>6}", self.piece_squares)?;
+        writeln!(f, "pawn structure  {:>6}", self.pawn_structure)?;
+        writeln!(f, "king safety     {:>6}", self.king_safety)?;
+        writeln!(f, "mobility        {:>6}", self.mobility)?;
+        writeln!(f, "bishop pair     {:>6}", self.bishop_pair)?;
+        writeln!(f, "phase           {:>6}", self.phase)?;
+        write!(f, "total           {:>6}", self.total())
+    }
+}
+
+/**
+ * Returns the material value of a piece kind in centipawns.
+ *
+ * @param kind - The piece kind to get the value of.
+ * @return The value of the piece kind, where kings are worth nothing as they cannot be captured.
+ */
+pub fn piece_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Pawn => 100,
+        PieceKind::Knight => 320,
+        PieceKind::Bishop => 330,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 0,
+    }
+}
+
+/**
+ * Evaluates the position from the point of view of the player to move.
+ *
+ * @param board - The position to evaluate, with up to date board information.
+ * @return The score in centipawns, positive if the player to move is better.
+ */
+pub fn evaluate(board: &Board) -> i32 {
+    breakdown(board).total()
+}
+
+/**
+ * Evaluates the position and returns every term of the evaluation separately.
+ *
+ * Each term is computed for both players, blended between its middlegame and endgame value by the game phase, and
+ * given from the point of view of the player to move.
+ *
+ * @param board - The position to evaluate, with up to date board information.
+ * @return The terms of the evaluation.
+ */
+pub fn breakdown(board: &Board) -> Evaluation {
+    let info = &board.board_info;
+    let phase = game_phase(board);
+    let sign = match from_idx(info.turn) {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+
+    let mut material = 0;
+    let mut piece_squares = Score::default();
+    for piece in board.squares.iter().flatten() {
+        let value = piece_value(piece.kind);
+        let square = piece_square(piece.kind, piece.color, piece.position);
+        if piece.color == Color::White {
+            material += value;
+            piece_squares += square;
+        } else {
+            material -= value;
+            piece_squares = piece_squares - square;
+        }
+    }
+    let term = |white: Score, black: Score| sign * (white - black).taper(phase);
+
+    Evaluation {
+        material: sign * material,
+        piece_squares: sign * piece_squares.taper(phase),
+        pawn_structure: term(
+            pawn_structure(info, Color::White),
+            pawn_structure(info, Color::Black),
+        ),
+        king_safety: term(
+            king_safety(info, Color::White),
+            king_safety(info, Color::Black),
+        ),
+        mobility: term(mobility(info, Color::White), mobility(info, Color::Black)),
+        bishop_pair: term(
+            bishop_pair(info, Color::White),
+            bishop_pair(info, Color::Black),
+        ),
+        phase,
+    }
+}
+
+/**
+ * Returns the game phase from the pieces left on the board.
+ *
+ * Knights and bishops count one, rooks two and queens four, so the starting position has `MAX_PHASE`.
+ *
+ * @param board - The position to get the game phase of.
+ * @return The game phase, from `MAX_PHASE` in the opening down to 0 when only kings and pawns are left.
+ */
+pub fn game_phase(board: &Board) -> i32 {
+    let phase = board
+        .squares
+        .iter()
+        .flatten()
+        .map(|piece| match piece.kind {
+            PieceKind::Knight | PieceKind::Bishop => 1,
+            PieceKind::Rook => 2,
+            PieceKind::Queen => 4,
+            PieceKind::Pawn | PieceKind::King => 0,
+        })
+        .sum::<i32>();
+    phase.min(MAX_PHASE)
+}
+
+/**
+ * Looks up the piece-square table value of a piece.
+ *
+ * @param kind - The kind of the piece.
+ * @param color - The color of the piece; Black's tables are White's mirrored vertically.
+ * @param position - The position of the piece.
+ * @return The middlegame and endgame value of the piece on its square.
+ */
+fn piece_square(kind: PieceKind, color: Color, position: Position) -> Score {
+    let (x, y) = (position.0 as usize, position.1 as usize);
+    let row = if color == Color::White { 7 - y } else { y };
+    let idx = row * 8 + x;
+    match kind {
+        PieceKind::Pawn => Score(PAWN_MG[idx], PAWN_EG[idx]),
+        PieceKind::Knight => Score(KNIGHT[idx], KNIGHT[idx]),
+        PieceKind::Bishop => Score(BISHOP[idx], BISHOP[idx]),
+        PieceKind::Rook => Score(ROOK[idx], ROOK[idx]),
+        PieceKind::Queen => Score(QUEEN[idx], QUEEN[idx]),
+        PieceKind::King => Score(KING_MG[idx], KING_EG[idx]),
+    }
+}
+
+/**
+ * Returns a bitboard of the given file and the files next to it.
+ *
+ * @param file - The file, from 0 for the a-file to 7 for the h-file.
+ * @return The bitboard of the file and its neighbours.
+ */
+fn adjacent_files(file: u8) -> Bitboard {
+    let mut files = FILE_A << file;
+    if file > 0 {
+        files |= FILE_A << (file - 1);
+    }
+    if file < 7 {
+        files |= FILE_A << (file + 1);
+    }
+    files
+}
+
+/**
+ * Returns a bitboard of the ranks in front of the given rank from the given player's point of view.
+ *
+ * @param rank - The rank, from 0 for the first rank to 7 for the eighth rank.
+ * @param color - The player whose direction of movement is forward.
+ * @return The bitboard of all ranks in front of the rank.
+ */
+fn ranks_in_front(rank: u8, color: Color) -> Bitboard {
+    match color {
+        Color::White if rank < 7 => !0 << ((rank + 1) * 8),
+        Color::Black => (1 << (rank * 8)) - 1,
+        Color::White => 0,
+    }
+}
+
+/**
+ * Scores the pawn structure of a player.
+ *
+ * Doubled and isolated pawns are penalised, and passed pawns get a bonus that grows as they advance.
+ *
+ * @param info - The board information with up to date bitboards.
+ * @param color - The player to score.
+ * @return The middlegame and endgame score of the player's pawns.
+ */
+fn pawn_structure(info: &BoardInfo, color: Color) -> Score {
+    let pawns = info.pawn(color);
+    let enemy_pawns = info.pawn(color.other());
+    let mut score = Score::default();
+
+    for file in 0..8u8 {
+        let on_file = (pawns & (FILE_A << file)).count_ones() as i32;
+        if on_file > 1 {
+            score += DOUBLED_PAWN.times(on_file - 1);
+        }
+        if on_file > 0 && pawns & adjacent_files(file) & !(FILE_A << file) == 0 {
+            score += ISOLATED_PAWN.times(on_file);
+        }
+    }
+
+    let mut remaining = pawns;
+    while remaining != 0 {
+        let idx = remaining.trailing_zeros() as u8;
+        remaining &= remaining - 1;
+        let (file, rank) = (idx % 8, idx / 8);
+        if enemy_pawns & adjacent_files(file) & ranks_in_front(rank, color) == 0 {
+            let relative_rank = if color == Color::White {
+                rank
+            } else {
+                7 - rank
+            };
+            let bonus = PASSED_PAWN[relative_rank as usize];
+            score += Score(bonus / 2, bonus);
+        }
+    }
+    score
+}
+
+/**
+ * Scores the safety of a player's king.
+ *
+ * Pawns shielding the king on the three files around it earn a bonus, a missing shield pawn a penalty, and every
+ * square next to the king that the opponent can move to is penalised. King safety only matters in the middlegame.
+ *
+ * @param info - The board information with up to date bitboards.
+ * @param color - The player to score.
+ * @return The middlegame and endgame score of the player's king safety.
+ */
+fn king_safety(info: &BoardInfo, color: Color) -> Score {
+    let (king_x, king_y) = info.king_pos(color);
+    let pawns = info.pawn(color);
+    let forward: i8 = if color == Color::White { 1 } else { -1 };
+    let mut score = 0;
+    let mut zone: Bitboard = 0;
+
+    for dx in -1..=1i8 {
+        let x = king_x as i8 + dx;
+        if !(0..8).contains(&x) {
+            continue;
+        }
+        let shield = (1..=2)
+            .find(|&distance| {
+                let y = king_y as i8 + forward * distance;
+                (0..8).contains(&y) && pawns & pos_to_bb((x as u8, y as u8)) != 0
+            })
+            .unwrap_or(0);
+        score += PAWN_SHIELD[shield as usize];
+
+        for dy in -1..=1i8 {
+            let y = king_y as i8 + dy;
+            if (0..8).contains(&y) && (dx, dy) != (0, 0) {
+                zone |= pos_to_bb((x as u8, y as u8));
+            }
+        }
+    }
+
+    let enemy_moves = info.get_color_move_bitboard(color.other());
+    score += KING_ZONE_ATTACK * (zone & enemy_moves).count_ones() as i32;
+    Score(score, 0)
+}
+
+/**
+ * Scores the mobility of a player from the squares their pieces can move to.
+ *
+ * @param info - The board information with up to date bitboards and attacks.
+ * @param color - The player to score.
+ * @return The middlegame and endgame score of the player's mobility.
+ */
+fn mobility(info: &BoardInfo, color: Color) -> Score {
+    let moves = info.get_color_move_bitboard(color);
+    MOBILITY.times(moves.count_ones() as i32)
+}
+
+/**
+ * Scores the bishop pair of a player.
+ *
+ * @param info - The board information with up to date bitboards.
+ * @param color - The player to score.
+ * @return The bishop pair bonus if the player has two or more bishops, zero otherwise.
+ */
+fn bishop_pair(info: &BoardInfo, color: Color) -> Score {
+    if info
+        .get_piece_bitboard(PieceKind::Bishop, color)
+        .count_ones()
+        >= 2
+    {
+        BISHOP_PAIR
+    } else {
+        Score::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::eval::{breakdown, evaluate, game_phase, MAX_PHASE};
+
+    /**
+     * Mirrors a position vertically and swaps the colors of all pieces and the player to move.
+     *
+     * @param fen - The piece placement and side to move of the position.
+     * @return The mirrored position, without castling rights or en passant target.
+     */
+    fn mirror(fen: &str) -> String {
+        let mut fields = fen.split_whitespace();
+        let placement = fields
+            .next()
+            .unwrap()
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let turn = if fields.next() == Some("w") { "b" } else { "w" };
+        format!("{} {} - - 0 1", placement, turn)
+    }
+
+    /**
+     * Evaluates the position given as FEN.
+     *
+     * @param fen - The position to evaluate.
+     * @return The evaluation of the position from the point of view of the player to move.
+     */
+    fn eval_fen(fen: &str) -> i32 {
+        evaluate(&Board::new_from_fen(fen).unwrap())
+    }
+
+    #[test]
+    /**
+     * Tests that the starting position is balanced and in the opening phase.
+     */
+    fn test_starting_position() {
+        let board = Board::new_standard();
+        let evaluation = breakdown(&board);
+        assert_eq!(evaluation.total(), 0);
+        assert_eq!(evaluation.material, 0);
+        assert_eq!(evaluation.phase, MAX_PHASE);
+        assert_eq!(
+            game_phase(&Board::new_from_fen("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1").unwrap()),
+            0
+        );
+    }
+
+    #[test]
+    /**
+     * Tests that the evaluation is the same for both players in mirrored positions.
+     */
+    fn test_symmetry() {
+        for fen in [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w",
+            "6k1/5ppp/8/8/2B5/8/1P3PPP/3R2K1 b",
+        ] {
+            let score = eval_fen(&format!("{} - - 0 1", fen));
+            assert_eq!(score, eval_fen(&mirror(fen)), "{}", fen);
+        }
+    }
+
+    #[test]
+    /**
+     * Tests that the evaluation is from the point of view of the player to move.
+     */
+    fn test_side_to_move() {
+        let white = breakdown(&Board::new_from_fen("3qk3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap());
+        let black = breakdown(&Board::new_from_fen("3qk3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap());
+        assert_eq!(white.material, -900);
+        assert_eq!(black.material, 900);
+        assert!(white.total() < 0 && black.total() > 0);
+    }
+
+    #[test]
+    /**
+     * Tests that doubled and isolated pawns are penalised and passed pawns rewarded.
+     */
+    fn test_pawn_structure() {
+        let healthy =
+            breakdown(&Board::new_from_fen("4k3/5ppp/8/8/8/8/5PPP/4K3 w - - 0 1").unwrap());
+        let doubled =
+            breakdown(&Board::new_from_fen("4k3/5ppp/8/8/8/5P2/5P1P/4K3 w - - 0 1").unwrap());
+        let passed =
+            breakdown(&Board::new_from_fen("4k3/5ppp/1P6/8/8/8/5PPP/4K3 w - - 0 1").unwrap());
+        assert_eq!(healthy.pawn_structure, 0);
+        assert!(doubled.pawn_structure < 0);
+        assert!(passed.pawn_structure > 0);
+    }
+
+    #[test]
+    /**
+     * Tests the king safety, mobility and bishop pair terms.
+     */
+    fn test_other_terms() {
+        let sheltered =
+            breakdown(&Board::new_from_fen("r2q2k1/5ppp/8/8/8/8/5PPP/R2Q2K1 w - - 0 1").unwrap());
+        let exposed =
+            breakdown(&Board::new_from_fen("r2q2k1/5ppp/8/8/8/5PPP/8/R2Q2K1 w - - 0 1").unwrap());
+        assert_eq!(sheltered.king_safety, 0);
+        assert!(exposed.king_safety < 0);
+
+        let pair = breakdown(&Board::new_from_fen("2b1kb2/8/8/8/8/8/8/2B1K1N1 w - - 0 1").unwrap());
+        assert!(pair.bishop_pair < 0);
+        assert!(pair.mobility != 0);
+    }
+}
@@ -1,17 +1,155 @@
 use crate::board::Board;
 use crate::game::player::{Color, Player};
 use crate::rules::r#move::Move;
+use std::fmt::Display;
 
 type MoveHistory = Vec<Move>;
 
+// Enum to represent the reasons a game can be drawn
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DrawReason {
+    Agreement,
+    FiftyMoveRule, // Claimable after 50 moves by each side without a capture or pawn move
+    SeventyFiveMoveRule, // Automatic after 75 moves by each side without a capture or pawn move
+    ThreefoldRepetition, // Claimable once the same position has occurred three times
+    FivefoldRepetition, // Automatic once the same position has occurred five times
+    InsufficientMaterial, // Automatic when neither side can checkmate
+}
+
+impl DrawReason {
+    /**
+     * Checks if the draw happens automatically rather than on a player's claim or agreement.
+     *
+     * @return true for the seventy-five-move rule, fivefold repetition and insufficient material, false otherwise.
+     */
+    pub fn is_automatic(&self) -> bool {
+        matches!(
+            self,
+            DrawReason::SeventyFiveMoveRule
+                | DrawReason::FivefoldRepetition
+                | DrawReason::InsufficientMaterial
+        )
+    }
+}
+
+// Enum to represent the status of a game
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate(Color), // Winner
+    Stalemate,
+    Draw(DrawReason),
+    Resignation(Color), // Winner
+    Timeout(Color),     // Winner
+}
+
+// Enum to represent the final result of a game
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameStatus {
+    /**
+     * Checks if the game has ended.
+     *
+     * @return true if the status is anything other than `GameStatus::Ongoing`, false otherwise.
+     */
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+
+    /**
+     * Returns the final result of the game.
+     *
+     * This function maps the status to the winning side, or to a draw for stalemates and drawn games.
+     *
+     * @return The result of the game, or None if the game is still ongoing.
+     */
+    pub fn result(&self) -> Option<GameResult> {
+        match self {
+            GameStatus::Ongoing => None,
+            GameStatus::Checkmate(winner)
+            | GameStatus::Resignation(winner)
+            | GameStatus::Timeout(winner) => Some(match winner {
+                Color::White => GameResult::WhiteWins,
+                Color::Black => GameResult::BlackWins,
+            }),
+            GameStatus::Stalemate | GameStatus::Draw(_) => Some(GameResult::Draw),
+        }
+    }
+}
+
+impl Display for DrawReason {
+    /**
+     * Formats the draw reason as a human readable description.
+     *
+     * @param f - The formatter to write the description to.
+     * @return Result - Ok if the formatting is successful, Err otherwise.
+     */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::Agreement => write!(f, "mutual agreement"),
+            DrawReason::FiftyMoveRule => write!(f, "the fifty-move rule"),
+            DrawReason::SeventyFiveMoveRule => write!(f, "the seventy-five-move rule"),
+            DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawReason::FivefoldRepetition => write!(f, "fivefold repetition"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material"),
+        }
+    }
+}
+
+impl Display for GameStatus {
+    /**
+     * Formats the game status as an announcement, such as "Checkmate, White wins".
+     *
+     * @param f - The formatter to write the announcement to.
+     * @return Result - Ok if the formatting is successful, Err otherwise.
+     */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameStatus::Ongoing => write!(f, "Game in progress"),
+            GameStatus::Checkmate(winner) => write!(f, "Checkmate, {:?} wins", winner),
+            GameStatus::Stalemate => write!(f, "Stalemate, the game is drawn"),
+            GameStatus::Draw(reason) => write!(f, "Draw by {}", reason),
+            GameStatus::Resignation(winner) => {
+                write!(f, "{:?} resigns, {:?} wins", winner.other(), winner)
+            }
+            GameStatus::Timeout(winner) => {
+                write!(f, "{:?} ran out of time, {:?} wins", winner.other(), winner)
+            }
+        }
+    }
+}
+
+impl Display for GameResult {
+    /**
+     * Formats the result in the notation used by PGN files and engine protocols.
+     *
+     * @param f - The formatter to write the result to.
+     * @return Result - Ok if the formatting is successful, Err otherwise.
+     */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameResult::WhiteWins => write!(f, "1-0"),
+            GameResult::BlackWins => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct GameState {
-    pub fen: String,               // FEN string
-    pub players: (Player, Player), // Tuple of players
-    pub turn: u8,                  // Turn counter
-    pub move_history: MoveHistory, // MoveHistory struct
-    pub white_in_check: bool,      // White in check flag
-    pub black_in_check: bool,      // Black in check flag
+    pub fen: String,                // FEN string
+    pub players: (Player, Player),  // Tuple of players
+    pub turn: u8,                   // Turn counter
+    pub move_history: MoveHistory,  // MoveHistory struct
+    pub white_in_check: bool,       // White in check flag
+    pub black_in_check: bool,       // Black in check flag
+    pub status: GameStatus,         // Ongoing or how the game ended
+    pub position_history: Vec<u64>, // Position key after each ply, starting with the initial position
 }
 
 impl Default for GameState {
@@ -43,6 +181,8 @@ impl GameState {
             move_history: Vec::new(),
             white_in_check: false,
             black_in_check: false,
+            status: GameStatus::Ongoing,
+            position_history: Vec::new(),
         }
     }
 
@@ -75,9 +215,80 @@ impl GameState {
         }
     }
 
+    /**
+     * Returns the player with the specified color.
+     *
+     * @param color - The color of the player.
+     * @return The player with the specified color.
+     */
+    pub fn player(&self, color: Color) -> &Player {
+        match color {
+            Color::White => &self.players.0,
+            Color::Black => &self.players.1,
+        }
+    }
+
+    /**
+     * Returns a mutable reference to the player with the specified color.
+     *
+     * @param color - The color of the player.
+     * @return The player with the specified color.
+     */
+    pub fn player_mut(&mut self, color: Color) -> &mut Player {
+        match color {
+            Color::White => &mut self.players.0,
+            Color::Black => &mut self.players.1,
+        }
+    }
+
+    /**
+     * Ends the game with a resignation.
+     *
+     * @param color - The color of the player who resigns.
+     */
+    pub fn resign(&mut self, color: Color) {
+        self.status = GameStatus::Resignation(color.other());
+    }
+
+    /**
+     * Ends the game because a player ran out of time.
+     *
+     * @param color - The color of the player whose clock ran out.
+     */
+    pub fn time_out(&mut self, color: Color) {
+        self.status = GameStatus::Timeout(color.other());
+    }
+
+    /**
+     * Ends the game in a draw agreed by both players.
+     */
+    pub fn agree_draw(&mut self) {
+        self.status = GameStatus::Draw(DrawReason::Agreement);
+    }
+
+    /**
+     * Counts how often the current position has occurred in the game.
+     *
+     * @return The number of times the last position in the position history has occurred, including now.
+     */
+    pub fn repetition_count(&self) -> usize {
+        match self.position_history.last() {
+            Some(key) => self.position_history.iter().filter(|k| *k == key).count(),
+            None => 0,
+        }
+    }
+
+    /**
+     * Undoes the last move of the game.
+     *
+     * This function takes the last move back on the board and removes it from the move history.
+     *
+     * @param board - The board to undo the move on.
+     */
     pub fn undo(&mut self, board: &mut Board) {
         self.move_history.pop();
-        self.fen = board.to_fen();
         board.undo_move();
+        board.update();
+        self.fen = board.to_fen();
     }
 }
@@ -2,10 +2,13 @@ pub mod game_state;
 pub mod player;
 
 use crate::board::piece::get_moves;
-use crate::board::{display_board, Board, Position};
-use crate::game::game_state::GameState;
-use crate::game::player::{from_idx, user_mv_idx, Color};
+use crate::board::{display_board, Board, FenError, Position};
+use crate::game::game_state::{DrawReason, GameState, GameStatus};
+use crate::game::player::{from_idx, read_user_move, to_san, Color, PlayerKind};
+use crate::rules::is_insufficient_material;
 use crate::rules::r#move::Move;
+use crate::search::SearchLimits;
+use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct Game {
@@ -52,10 +55,38 @@ impl Game {
      * @return A new Chessboard instance with a standard starting configuration.
      */
     pub fn new_standard() -> Self {
-        Self {
-            board: Board::new_standard(),
-            game_state: GameState::new(),
-        }
+        Self::new_from_board(Board::new_standard())
+    }
+
+    /**
+     * Creates a new game starting from the position described by the given FEN string.
+     *
+     * @param fen - The FEN string representing the starting position.
+     * @return A new game starting from the given position, or the reason the FEN string was rejected.
+     */
+    pub fn new_from_fen(fen: &str) -> Result<Self, FenError> {
+        Ok(Self::new_from_board(Board::new_from_fen(fen)?))
+    }
+
+    /**
+     * Creates a new game starting from the given board.
+     *
+     * This function initializes the game state from the board, so the FEN string, side to move, check flags and
+     * status describe the starting position.
+     *
+     * @param board - The board to start the game from.
+     * @return A new game starting from the given board.
+     */
+    pub fn new_from_board(board: Board) -> Self {
+        let mut game_state = GameState::new();
+        game_state.fen = board.to_fen();
+        game_state.turn = board.board_info.turn;
+        game_state.white_in_check = board.board_info.is_in_check(Color::White);
+        game_state.black_in_check = board.board_info.is_in_check(Color::Black);
+        game_state.position_history.push(board.position_key());
+        let mut game = Self { board, game_state };
+        game.game_state.status = game_status(&game);
+        game
     }
 }
 /**
@@ -64,16 +95,15 @@ impl Game {
  * This function takes the current game state and a move as input, and updates the game state accordingly.
  * It performs the following steps:
  * 1. Creates a mutable copy of the game state.
- * 2. Sets the FEN string of the game state to the current board state.
- * 3. Makes the specified move on the board.
- * 4. Appends the move to the move history.
- * 5. Updates the 'white_in_check' flag based on whether the white player is in check.
- * 6. Updates the 'black_in_check' flag based on whether the black player is in check.
- * 7. Advances the turn to the next player.
- * 8. Updates the game state with the new game state.
- * 9. Updates the board state.
- * 10. Copies the move history from the board info to the game state.
- * 11. Returns the updated game state.
+ * 2. Updates the board state.
+ * 3. Sets the FEN string of the game state to the current board state.
+ * 4. Updates the 'white_in_check' flag based on whether the white player is in check.
+ * 5. Updates the 'black_in_check' flag based on whether the black player is in check.
+ * 6. Advances the turn to the next player.
+ * 7. Copies the move history from the board info to the game state.
+ * 8. Records the position in the position history, replacing any positions from undone moves.
+ * 9. Determines whether the game has ended.
+ * 10. Returns the updated game state.
  *
  * @param game - The current game state.
  * @param mv - The move to be made.
@@ -81,69 +111,212 @@ impl Game {
  */
 pub fn update(game: Game) -> Game {
     let mut game = game;
-    let mut gs = &mut game.game_state;
+    game.board.update();
+    let gs = &mut game.game_state;
     gs.fen = game.board.to_fen();
     gs.white_in_check = game.board.board_info.is_in_check(Color::White);
     gs.black_in_check = game.board.board_info.is_in_check(Color::Black);
     gs.next_turn();
-    game.game_state = gs.clone();
-    game.board.update();
     game.game_state.move_history = game.board.board_info.move_history.clone();
+
+    let plies = game.board.board_info.move_history.len();
+    let position_history = &mut game.game_state.position_history;
+    position_history.truncate(plies);
+    position_history.push(game.board.position_key());
+
+    game.game_state.status = game_status(&game);
     game
 }
 
 /**
- * Plays the game by repeatedly getting user moves and applying them to the game board.
+ * Determines the status of the game from the current position.
+ *
+ * The player to move is checkmated if they have no legal moves while in check, and stalemated if they
+ * have no legal moves otherwise. Otherwise the game is drawn automatically by the seventy-five-move rule,
+ * fivefold repetition or insufficient material. Resignations, timeouts, agreed draws and claimed draws are
+ * kept once they have been recorded.
+ *
+ * @param game - The game to determine the status of.
+ * @return The status of the game.
+ */
+pub fn game_status(game: &Game) -> GameStatus {
+    match game.game_state.status {
+        GameStatus::Resignation(_) | GameStatus::Timeout(_) => return game.game_state.status,
+        GameStatus::Draw(reason) if !reason.is_automatic() => return game.game_state.status,
+        _ => (),
+    }
+
+    let board = &game.board;
+    if board.board_info.valid_moves.is_empty() {
+        let to_move = from_idx(board.board_info.turn);
+        return if board.board_info.is_in_check(to_move) {
+            GameStatus::Checkmate(to_move.other())
+        } else {
+            GameStatus::Stalemate
+        };
+    }
+
+    if board.board_info.halfmove_clock >= 150 {
+        GameStatus::Draw(DrawReason::SeventyFiveMoveRule)
+    } else if game.game_state.repetition_count() >= 5 {
+        GameStatus::Draw(DrawReason::FivefoldRepetition)
+    } else if is_insufficient_material(&board.squares) {
+        GameStatus::Draw(DrawReason::InsufficientMaterial)
+    } else {
+        GameStatus::Ongoing
+    }
+}
+
+/**
+ * Returns the draw the player to move could claim in the current position.
+ *
+ * A draw can be claimed once 50 moves by each side have been played without a capture or pawn move, or once
+ * the current position has occurred three times.
+ *
+ * @param game - The game to check.
+ * @return The reason a draw can be claimed, or None if the game is over or no draw can be claimed.
+ */
+pub fn claimable_draw(game: &Game) -> Option<DrawReason> {
+    if game.game_state.status.is_over() {
+        None
+    } else if game.game_state.repetition_count() >= 3 {
+        Some(DrawReason::ThreefoldRepetition)
+    } else if game.board.board_info.halfmove_clock >= 100 {
+        Some(DrawReason::FiftyMoveRule)
+    } else {
+        None
+    }
+}
+
+/**
+ * Claims a draw for the player to move, if one is available.
+ *
+ * @param game - The game in which to claim the draw.
+ * @return The reason the game was drawn, or None if no draw could be claimed.
+ */
+pub fn claim_draw(game: &mut Game) -> Option<DrawReason> {
+    let reason = claimable_draw(game)?;
+    game.game_state.status = GameStatus::Draw(reason);
+    Some(reason)
+}
+
+/**
+ * Returns the limits for a computer player's search from the game clock.
+ *
+ * Both players' clocks and increments are passed on, so the search budgets its time like under a GUI. Without a
+ * clock the computer player spends a second on every move.
+ *
+ * @param game - The game the computer player is to move in.
+ * @param color - The color of the computer player.
+ * @return The limits for the computer player's search.
+ */
+pub fn clock_limits(game: &Game, color: Color) -> SearchLimits {
+    if !game.game_state.player(color).timer.is_timed() {
+        return SearchLimits {
+            movetime: Some(Duration::from_secs(1)),
+            ..SearchLimits::default()
+        };
+    }
+    let white = &game.game_state.player(Color::White).timer;
+    let black = &game.game_state.player(Color::Black).timer;
+    SearchLimits {
+        wtime: Some(white.time),
+        btime: Some(black.time),
+        winc: white.increment,
+        binc: black.increment,
+        ..SearchLimits::default()
+    }
+}
+
+/**
+ * Plays the game by repeatedly getting moves from the players and applying them to the game board.
  *
  * This function implements the main game loop. It displays the current state of the board,
- * gets a move input from the user, applies the move to the game board, and updates the valid moves.
- * It continues this loop until the game is over.
+ * gets a move from the user or searches one for a computer player, applies the move to the game board,
+ * and updates the valid moves.
+ * The time every move takes is counted down from the player's clock, and a player whose clock runs out loses.
+ * It continues this loop until the game is over, and then announces how the game ended.
  *
  * @param game - The game object representing the current state of the game.
  */
 pub fn play(mut game: Game) {
-    game.board.board_info.valid_moves = game.board.get_current_moves();
     loop {
         display_board(&game.board);
-        let mv_idx = user_mv_idx();
-        let from: Position = (mv_idx.0, mv_idx.1);
-        let to: Position = (mv_idx.2, mv_idx.3);
-        let result = apply_move(game.clone(), from, to);
-        if let Ok(g) = result {
-            game = g;
-        } else {
-            println!("Invalid move!");
+        if game.game_state.status.is_over() {
+            println!("{}", game.game_state.status);
+            return;
+        }
+        let to_move = from_idx(game.board.board_info.turn);
+        let limits = clock_limits(&game, to_move);
+        let started = Instant::now();
+        let mv = match &mut game.game_state.player_mut(to_move).kind {
+            PlayerKind::Computer(brain) => {
+                let Some(mv) = brain.best_move(&game.board, &limits).best_move else {
+                    return;
+                };
+                println!("{:?} plays {}", to_move, to_san(&game.board, &mv));
+                mv
+            }
+            PlayerKind::Human => {
+                let Some(mv) = read_user_move(&game.board) else {
+                    return;
+                };
+                mv
+            }
+        };
+        if !game
+            .game_state
+            .player_mut(to_move)
+            .timer
+            .spend(started.elapsed())
+        {
+            game.game_state.time_out(to_move);
+            continue;
         }
-        game.board.board_info.valid_moves = game.board.get_current_moves();
+        game = apply_legal_move(game, mv).expect("the move was read from the legal moves");
     }
 }
 
 pub fn undo_move(game: Game) -> Game {
     let mut game = game;
-    let last_move = game.game_state.move_history.pop();
-    if last_move.is_some() {
+    if !game.game_state.move_history.is_empty() {
         game.game_state.undo(&mut game.board);
-        game.game_state.next_turn();
         game = update(game);
     }
     game
 }
 
+#[allow(clippy::result_unit_err)]
 pub fn apply_move(game: Game, from: Position, to: Position) -> Result<Game, ()> {
+    let mv = game
+        .board
+        .board_info
+        .valid_moves
+        .iter()
+        .find(|mv| mv.from == from && mv.to == to)
+        .cloned()
+        .ok_or(())?;
+    apply_legal_move(game, mv)
+}
+
+/**
+ * Plays the given move in the game.
+ *
+ * Unlike `apply_move`, this function plays exactly the given move, so the promotion piece can be chosen.
+ *
+ * @param game - The game to play the move in.
+ * @param mv - The move to play, which must be one of the legal moves of the game.
+ * @return The game after the move, or Err if the game is over or the move is not legal.
+ */
+#[allow(clippy::result_unit_err)]
+pub fn apply_legal_move(game: Game, mv: Move) -> Result<Game, ()> {
     let mut game = game;
-    let from_square = game.board.get_piece(from);
-    let moves = game.board.board_info.valid_moves.clone();
-    if from_square.is_some() {
-        for mv in moves {
-            if mv.to == to {
-                game.board.make_move(mv.clone());
-                game.game_state.move_history.push(mv);
-                game = update(game);
-                return Ok(game);
-            }
-        }
+    if game.game_state.status.is_over() || !game.board.board_info.valid_moves.contains(&mv) {
+        return Err(());
     }
-    Err(())
+    game.board.make_move(mv.clone());
+    game.game_state.move_history.push(mv);
+    Ok(update(game))
 }
 
 pub fn get_color_moves(board: &Board, color: Color) -> Vec<Move> {
@@ -173,3 +346,265 @@ pub fn get_all_moves(board: &Board) -> Vec<Move> {
     }
     moves
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::game_state::{DrawReason, GameResult, GameStatus};
+    use crate::game::player::Color;
+    use crate::game::{apply_move, claim_draw, claimable_draw, clock_limits, undo_move, Game};
+    use std::time::Duration;
+
+    #[test]
+    /**
+     * Tests that the fool's mate ends the game in checkmate.
+     *
+     * This function plays 1. f3 e5 2. g4 Qh4# and asserts that the game is ongoing until the last move,
+     * that Black is then reported as the winner and that no further moves are accepted.
+     */
+    fn test_checkmate() {
+        let mut game = Game::new_standard();
+        let moves = [((5, 1), (5, 2)), ((4, 6), (4, 4)), ((6, 1), (6, 3))];
+        for (from, to) in moves {
+            game = apply_move(game, from, to).unwrap();
+            assert_eq!(game.game_state.status, GameStatus::Ongoing);
+        }
+
+        game = apply_move(game, (3, 7), (7, 3)).unwrap();
+        assert!(game.game_state.white_in_check);
+        assert_eq!(game.game_state.status, GameStatus::Checkmate(Color::Black));
+        assert_eq!(game.game_state.status.result(), Some(GameResult::BlackWins));
+        assert_eq!(game.game_state.status.to_string(), "Checkmate, Black wins");
+        assert!(apply_move(game, (4, 0), (5, 1)).is_err());
+    }
+
+    #[test]
+    /**
+     * Tests that a position without legal moves and without check is a stalemate.
+     */
+    fn test_stalemate() {
+        let game = Game::new_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(game.game_state.status, GameStatus::Stalemate);
+        assert_eq!(game.game_state.status.result(), Some(GameResult::Draw));
+
+        let game = Game::new_from_fen("7k/8/6K1/5Q2/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(game.game_state.status, GameStatus::Ongoing);
+        let game = apply_move(game, (5, 4), (5, 6)).unwrap();
+        assert_eq!(game.game_state.status, GameStatus::Stalemate);
+    }
+
+    #[test]
+    /**
+     * Tests that resignations, timeouts and agreed draws end the game and survive later updates.
+     */
+    fn test_resignation_and_timeout() {
+        let mut game = Game::new_standard();
+        game.game_state.resign(Color::White);
+        assert_eq!(
+            game.game_state.status,
+            GameStatus::Resignation(Color::Black)
+        );
+        assert!(apply_move(game, (4, 1), (4, 3)).is_err());
+
+        let mut game = Game::new_standard();
+        game = apply_move(game, (4, 1), (4, 3)).unwrap();
+        game.game_state.time_out(Color::Black);
+        assert_eq!(game.game_state.status, GameStatus::Timeout(Color::White));
+        assert_eq!(game.game_state.status.result(), Some(GameResult::WhiteWins));
+        game = undo_move(game);
+        assert!(game.game_state.status.is_over());
+
+        let mut game = Game::new_standard();
+        game.game_state.agree_draw();
+        assert_eq!(game.game_state.status.result(), Some(GameResult::Draw));
+        assert_eq!(
+            game.game_state.status.to_string(),
+            "Draw by mutual agreement"
+        );
+    }
+
+    #[test]
+    /**
+     * Tests that the fifty-move rule can be claimed and the seventy-five-move rule ends the game.
+     *
+     * This function starts from positions one move away from each limit, plays a quiet king move and checks
+     * that the claimable and automatic draws appear only once the halfmove clock reaches them.
+     */
+    fn test_fifty_and_seventy_five_move_rules() {
+        let game = Game::new_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert_eq!(claimable_draw(&game), None);
+        let mut game = apply_move(game, (4, 0), (3, 0)).unwrap();
+        assert_eq!(game.game_state.status, GameStatus::Ongoing);
+        assert_eq!(claimable_draw(&game), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(claim_draw(&mut game), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(game.game_state.status.result(), Some(GameResult::Draw));
+        assert!(apply_move(game, (4, 7), (4, 6)).is_err());
+
+        let game = Game::new_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 100").unwrap();
+        assert_eq!(game.game_state.status, GameStatus::Ongoing);
+        let game = apply_move(game, (4, 0), (3, 0)).unwrap();
+        assert_eq!(
+            game.game_state.status,
+            GameStatus::Draw(DrawReason::SeventyFiveMoveRule)
+        );
+
+        // A capture resets the clock, so no draw is available afterwards.
+        let game = Game::new_from_fen("4k3/8/8/8/8/8/r7/R3K3 w - - 120 100").unwrap();
+        let game = apply_move(game, (0, 0), (0, 1)).unwrap();
+        assert_eq!(game.board.board_info.halfmove_clock, 0);
+        assert_eq!(claimable_draw(&game), None);
+    }
+
+    #[test]
+    /**
+     * Tests threefold and fivefold repetition by shuffling both knights back and forth.
+     *
+     * Each shuffle of Ng1-f3, Ng8-f6, Nf3-g1, Nf6-g8 repeats the starting position once more.
+     */
+    fn test_repetition() {
+        let shuffle = [
+            ((6, 0), (5, 2)),
+            ((6, 7), (5, 5)),
+            ((5, 2), (6, 0)),
+            ((5, 5), (6, 7)),
+        ];
+        let mut game = Game::new_standard();
+        for (from, to) in shuffle {
+            game = apply_move(game, from, to).unwrap();
+        }
+        assert_eq!(game.game_state.repetition_count(), 2);
+        assert_eq!(claimable_draw(&game), None);
+
+        for (from, to) in shuffle {
+            game = apply_move(game, from, to).unwrap();
+        }
+        assert_eq!(game.game_state.repetition_count(), 3);
+        assert_eq!(claimable_draw(&game), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(game.game_state.status, GameStatus::Ongoing);
+
+        for _ in 0..2 {
+            for (from, to) in shuffle {
+                game = apply_move(game, from, to).unwrap();
+            }
+        }
+        assert_eq!(game.game_state.repetition_count(), 5);
+        assert_eq!(
+            game.game_state.status,
+            GameStatus::Draw(DrawReason::FivefoldRepetition)
+        );
+    }
+
+    #[test]
+    /**
+     * Tests that the position key includes castling and en passant rights.
+     *
+     * The en passant square only counts when a capture is actually possible, and positions that differ only in
+     * castling rights are different positions.
+     */
+    fn test_position_key_identity() {
+        let with_ep = Game::new_from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let without_ep = Game::new_from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 2").unwrap();
+        assert_ne!(
+            with_ep.board.position_key(),
+            without_ep.board.position_key()
+        );
+
+        let no_capture = Game::new_from_fen("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 2").unwrap();
+        let no_target = Game::new_from_fen("4k3/8/8/3p4/8/8/8/4K3 w - - 0 2").unwrap();
+        assert_eq!(
+            no_capture.board.position_key(),
+            no_target.board.position_key()
+        );
+
+        let castling = Game::new_from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let no_castling = Game::new_from_fen("4k3/8/8/8/8/8/8/R3K2R w - - 0 1").unwrap();
+        assert_ne!(
+            castling.board.position_key(),
+            no_castling.board.position_key()
+        );
+    }
+
+    #[test]
+    /**
+     * Tests that dead positions are drawn automatically while positions with mating material are not.
+     */
+    fn test_insufficient_material() {
+        let drawn = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+            "2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1",
+        ];
+        for fen in drawn {
+            let game = Game::new_from_fen(fen).unwrap();
+            assert_eq!(
+                game.game_state.status,
+                GameStatus::Draw(DrawReason::InsufficientMaterial),
+                "{fen}"
+            );
+        }
+
+        let playable = [
+            "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+            "1n2k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+            "3bk3/8/8/8/8/8/8/3BK3 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        ];
+        for fen in playable {
+            let game = Game::new_from_fen(fen).unwrap();
+            assert_eq!(game.game_state.status, GameStatus::Ongoing, "{fen}");
+        }
+
+        // Capturing the last pawn leaves two bare kings.
+        let game = Game::new_from_fen("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1").unwrap();
+        let game = apply_move(game, (4, 0), (3, 1)).unwrap();
+        assert_eq!(
+            game.game_state.status,
+            GameStatus::Draw(DrawReason::InsufficientMaterial)
+        );
+    }
+
+    #[test]
+    /**
+     * Tests that undoing a move takes back exactly one move and gives the turn back.
+     */
+    fn test_undo_move() {
+        let start = Game::new_standard();
+        let mut game = apply_move(Game::new_standard(), (4, 1), (4, 3)).unwrap();
+        game = apply_move(game, (3, 6), (3, 4)).unwrap();
+        let after_first = game.game_state.fen.clone();
+        game = apply_move(game, (4, 3), (3, 4)).unwrap();
+
+        game = undo_move(game);
+        assert_eq!(game.game_state.fen, after_first);
+        assert_eq!(game.game_state.move_history.len(), 2);
+        assert_eq!(game.game_state.turn, 0);
+
+        game = undo_move(undo_move(game));
+        assert_eq!(game.game_state.fen, start.game_state.fen);
+        assert_eq!(game.game_state.move_history.len(), 0);
+        assert_eq!(game.game_state.turn, 0);
+        assert_eq!(game.game_state.position_history.len(), 1);
+    }
+
+    #[test]
+    /**
+     * Tests that computer players search with the game clock, or a second per move without one.
+     */
+    fn test_clock_limits() {
+        let mut game = Game::new_standard();
+        let limits = clock_limits(&game, Color::White);
+        assert_eq!(limits.movetime, Some(Duration::from_secs(1)));
+        assert_eq!(limits.wtime, None);
+
+        game.game_state.player_mut(Color::White).timer.time = Duration::from_secs(300);
+        game.game_state.player_mut(Color::White).timer.increment = Duration::from_secs(3);
+        game.game_state.player_mut(Color::Black).timer.time = Duration::from_secs(200);
+        let limits = clock_limits(&game, Color::White);
+        assert_eq!(limits.movetime, None);
+        assert_eq!(limits.wtime, Some(Duration::from_secs(300)));
+        assert_eq!(limits.btime, Some(Duration::from_secs(200)));
+        assert_eq!(limits.winc, Duration::from_secs(3));
+        assert_eq!(limits.binc, Duration::ZERO);
+        assert!(limits.time_budget(Color::White).unwrap().soft < Duration::from_secs(300));
+    }
+}
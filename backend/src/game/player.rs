@@ -1,5 +1,12 @@
 use crate::board::piece::PieceKind;
-use crate::board::Board;
+use crate::board::{algebraic_from_pos, pos_from_algebraic, Board, Position};
+use crate::rules::r#move::{CastleType, Move, MoveType, UciMoveError};
+use crate::search::tt::TranspositionTable;
+use crate::search::{search, Reporter, SearchLimits, SearchResult};
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -61,6 +68,19 @@ pub struct Timer {
     pub increment: Duration,
 }
 
+impl Default for Timer {
+    /**
+     * Creates a new instance of the Timer struct with default values.
+     *
+     * This function returns a new Timer using the `new` function.
+     *
+     * @return A new Timer with no time and no increment.
+     */
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Timer {
     /**
      * Creates a new instance of the ChessClock struct.
@@ -94,18 +114,51 @@ impl Timer {
     pub fn reset(&mut self) {
         self.time = Duration::new(0, 0);
     }
+
+    /**
+     * Checks if the clock is in use, which it is once it has time or an increment.
+     *
+     * @return true if the player plays with a clock, false if the player has unlimited time.
+     */
+    pub fn is_timed(&self) -> bool {
+        !self.time.is_zero() || !self.increment.is_zero()
+    }
+
+    /**
+     * Counts the time a move took down from the clock and adds the increment.
+     *
+     * A clock that is not in use stays untouched.
+     *
+     * @param elapsed - The time the player took for the move.
+     * @return false if the clock ran out before the move was played, true otherwise.
+     */
+    pub fn spend(&mut self, elapsed: Duration) -> bool {
+        if !self.is_timed() {
+            return true;
+        }
+        if elapsed >= self.time {
+            self.time = Duration::ZERO;
+            return false;
+        }
+        self.time -= elapsed;
+        self.increment();
+        true
+    }
 }
 
 #[derive(Clone)]
 pub enum PlayerKind {
     Human,
-    Computer(Brain),
+    Computer(Box<Brain>),
 }
 
 #[derive(Clone)]
 pub struct Brain {
     pub board: Board,
     pub color: Color,
+    pub stop: Arc<AtomicBool>,      // Set to end the current search early
+    pub reporter: Option<Reporter>, // Called with the result of every completed iteration
+    pub tt: Arc<Mutex<TranspositionTable>>, // Positions searched so far, kept between searches
 }
 
 impl Brain {
@@ -120,7 +173,44 @@ impl Brain {
      * @return A new instance of the `ChessGame` struct.
      */
     pub fn new(board: Board, color: Color) -> Self {
-        Self { board, color }
+        Self::with_table(
+            board,
+            color,
+            Arc::new(Mutex::new(TranspositionTable::default())),
+        )
+    }
+
+    /**
+     * Creates a new brain that shares the given transposition table.
+     *
+     * @param board - The initial board state for the chess game.
+     * @param color - The player color for the chess game.
+     * @param tt - The transposition table to search with.
+     * @return A new brain searching with the given table.
+     */
+    pub fn with_table(board: Board, color: Color, tt: Arc<Mutex<TranspositionTable>>) -> Self {
+        Self {
+            board,
+            color,
+            stop: Arc::new(AtomicBool::new(false)),
+            reporter: None,
+            tt,
+        }
+    }
+
+    /**
+     * Searches the given position for the best move.
+     *
+     * The search ends when one of the limits is reached or the stop flag is set. The brain's transposition table
+     * is locked for the whole search.
+     *
+     * @param board - The position to search, with up to date legal moves.
+     * @param limits - The limits the search has to respect.
+     * @return The best move found with its score, depth, node count and principal variation.
+     */
+    pub fn best_move(&mut self, board: &Board, limits: &SearchLimits) -> SearchResult {
+        let mut tt = self.tt.lock().unwrap();
+        search(board, limits, &self.stop, self.reporter.as_ref(), &mut tt)
     }
 }
 
@@ -189,61 +279,555 @@ pub fn parse_promotion(input: &str) -> PieceKind {
 
 // Function to get a move from the user and parse it into a Move struct
 /**
- * Reads user input and returns the move indices.
+ * Reads moves from the user until a legal one is entered.
  *
- * This function prompts the user to enter their move in the format "e2-e4" and reads their input
- * from the standard input. It then parses the input and extracts the indices for the 'from' and 'to'
- * squares of the move.
+ * This function prompts the user to enter their move and reads it from the standard input. The move may be
+ * given in UCI coordinate notation ("e2e4", "e7e8q"), with an optional dash between the squares ("e2-e4"),
+ * or in Standard Algebraic Notation ("e4", "Nf3"). Moves that cannot be understood or are illegal are reported
+ * and the user is asked again.
  *
- * @return A tuple containing the 'from' and 'to' indices as (from_file, from_rank, to_file, to_rank).
+ * @param board - The board the move is played on, with up to date legal moves.
+ * @return The legal move entered by the user, or None if the input has ended.
  */
-pub fn user_mv_idx() -> (u8, u8, u8, u8) {
-    let mut input = String::new();
-    println!("Enter your move: (e2-e4) ");
-    std::io::stdin().read_line(&mut input).unwrap();
+pub fn read_user_move(board: &Board) -> Option<Move> {
+    loop {
+        let mut input = String::new();
+        println!("Enter your move: (e2e4, e7e8q or Nf3) ");
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => (),
+        }
 
-    // Temporarily Parse the move manually for now
-    let chars = input.chars().collect::<Vec<char>>();
-    let from = (chars[0] as u8 - b'a', chars[1] as u8 - b'1');
-    let to = (chars[3] as u8 - b'a', chars[4] as u8 - b'1');
+        let input = input.trim();
+        let coordinates = input.replacen('-', "", 1);
+        match Move::from_uci(board, &coordinates) {
+            Ok(m) => return Some(m),
+            Err(UciMoveError::Illegal(_)) => println!("'{}' is not a legal move", input),
+            Err(UciMoveError::Malformed(_)) => match parse_move(board, input) {
+                Ok(m) => return Some(m),
+                Err(error) => println!("{}", error),
+            },
+        }
+    }
+}
+
+// Enum to represent the reasons a move in Standard Algebraic Notation can be rejected
+#[derive(Clone, PartialEq, Debug)]
+pub enum SanError {
+    Empty,
+    Malformed(String),
+    MissingPromotion(String),
+    Illegal(String),
+    Ambiguous(String, Vec<Position>),
+}
 
-    (from.0, from.1, to.0, to.1)
+impl Display for SanError {
+    /**
+     * Formats the SAN error as a human readable message.
+     *
+     * @param f - The formatter to write the message to.
+     * @return Result - Ok if the formatting is successful, Err otherwise.
+     */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanError::Empty => write!(f, "no move was given"),
+            SanError::Malformed(san) => write!(f, "'{}' is not a move in algebraic notation", san),
+            SanError::MissingPromotion(san) => {
+                write!(f, "'{}' must name the piece to promote to, such as =Q", san)
+            }
+            SanError::Illegal(san) => write!(f, "'{}' is not a legal move in this position", san),
+            SanError::Ambiguous(san, froms) => {
+                let squares: Vec<String> =
+                    froms.iter().map(|&pos| algebraic_from_pos(pos)).collect();
+                write!(
+                    f,
+                    "'{}' is ambiguous, it could be played from {}",
+                    san,
+                    squares.join(" or ")
+                )
+            }
+        }
+    }
 }
 
+impl Error for SanError {}
+
 // Function to parse a move from a string according to the algebraic notation
 // For example, the move "e4" would be parsed as a the current turn's pawn on e2 moving to e4
-// The move "Nf3" would be parsed as the current turn's knight on b1 moving to f3
-// The move "Bxe5" would be parsed as the current turn's bishop on c3 capturing the opponent's pawn on e5
+// The move "Nf3" would be parsed as the current turn's knight on g1 moving to f3
+// The move "Bxe5" would be parsed as the current turn's bishop capturing the opponent's piece on e5
 /**
- * Parses a chess move from an input string.
+ * Parses a chess move in Standard Algebraic Notation (SAN).
  *
- * This function takes an input string and attempts to parse a chess move from it. It returns
- * a tuple representing the origin and destination squares of the move.
+ * This function resolves the move against the legal moves of the player to move on the given board. It accepts
+ * piece moves ("Nf3", "Bxe5"), pawn moves and captures ("e4", "exd6"), promotions ("e8=Q"), castling ("O-O",
+ * "O-O-O", also written with zeros) and moves disambiguated by file, rank or both ("Nbd7", "R1a3", "Qh4e1").
+ * Check, mate and annotation suffixes such as "+", "#" and "!?" are ignored.
  *
- * @param _input - The input string to parse the chess move from.
- * @return A tuple containing the origin and destination squares of the move.
- *
- * TODO: Implement the parsing logic for the chess move.
+ * @param board - The board the move is played on, with up to date legal moves.
+ * @param input - The move in Standard Algebraic Notation.
+ * @return The legal move described by the input, or the reason the input could not be resolved.
  */
-pub fn parse_move(_input: &str) -> (u8, u8, u8, u8) {
-    // Error handling
+pub fn parse_move(board: &Board, input: &str) -> Result<Move, SanError> {
+    let san = input.trim();
+    let body = san.trim_end_matches(['+', '#', '!', '?']);
+    if body.is_empty() {
+        return Err(if san.is_empty() {
+            SanError::Empty
+        } else {
+            SanError::Malformed(san.to_string())
+        });
+    }
+    let malformed = || SanError::Malformed(san.to_string());
 
-    // ... parse the move ...
-    (0, 0, 0, 0)
+    let castle_type = match body {
+        "O-O" | "0-0" => Some(CastleType::KingSide),
+        "O-O-O" | "0-0-0" => Some(CastleType::QueenSide),
+        _ => None,
+    };
+    if let Some(castle_type) = castle_type {
+        return board
+            .board_info
+            .valid_moves
+            .iter()
+            .find(|m| m.move_type == MoveType::Castle(castle_type))
+            .cloned()
+            .ok_or_else(|| SanError::Illegal(san.to_string()));
+    }
+
+    let mut chars: Vec<char> = body.chars().collect();
+    let kind = match chars[0] {
+        'K' => PieceKind::King,
+        'Q' => PieceKind::Queen,
+        'R' => PieceKind::Rook,
+        'B' => PieceKind::Bishop,
+        'N' => PieceKind::Knight,
+        _ => PieceKind::Pawn,
+    };
+    if kind != PieceKind::Pawn {
+        chars.remove(0);
+    }
+
+    // Promotion suffix, written as "=Q" or just "Q"
+    let mut promotion = None;
+    if let Some(&last) = chars.last() {
+        if "QRBN".contains(last) {
+            promotion = Some(parse_promotion(&last.to_string()));
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+    }
+    if promotion.is_some() && kind != PieceKind::Pawn {
+        return Err(malformed());
+    }
+
+    if chars.len() < 2 {
+        return Err(malformed());
+    }
+    let to_name: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let to = pos_from_algebraic(&to_name).ok_or_else(malformed)?;
+    let is_capture = chars.last() == Some(&'x');
+    if is_capture {
+        chars.pop();
+    }
+
+    // Whatever is left disambiguates the square the piece moves from
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars {
+        match c {
+            'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                from_file = Some(c as u8 - b'a')
+            }
+            '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+            _ => return Err(malformed()),
+        }
+    }
+    if kind == PieceKind::Pawn && (from_rank.is_some() || (is_capture && from_file.is_none())) {
+        return Err(malformed());
+    }
+
+    let candidates: Vec<&Move> = board
+        .board_info
+        .valid_moves
+        .iter()
+        .filter(|m| {
+            m.from_piece.kind == kind
+                && m.to == to
+                && from_file.is_none_or(|x| m.from.0 == x)
+                && from_rank.is_none_or(|y| m.from.1 == y)
+                && (!is_capture || m.is_capture())
+                && !matches!(m.move_type, MoveType::Castle(_))
+        })
+        .collect();
+
+    match promotion {
+        Some(kind) => {
+            let m = candidates.iter().find(|m| m.promotion() == Some(kind));
+            m.map(|m| (*m).clone())
+                .ok_or_else(|| SanError::Illegal(san.to_string()))
+        }
+        None if candidates.iter().any(|m| m.promotion().is_some()) => {
+            Err(SanError::MissingPromotion(san.to_string()))
+        }
+        None => match candidates.as_slice() {
+            [] => Err(SanError::Illegal(san.to_string())),
+            [m] => Ok((*m).clone()),
+            _ => Err(SanError::Ambiguous(
+                san.to_string(),
+                candidates.iter().map(|m| m.from).collect(),
+            )),
+        },
+    }
+}
+
+// Enum to represent the ways pieces can be written in Standard Algebraic Notation
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PieceNotation {
+    Letters,              // English letters: K, Q, R, B, N
+    Figurine,             // Figurines: ♔, ♕, ♖, ♗, ♘
+    Localized([char; 5]), // Letters for the king, queen, rook, bishop and knight, e.g. ['K', 'D', 'T', 'L', 'S']
+}
+
+impl PieceNotation {
+    /**
+     * Returns the symbol used for the given piece kind.
+     *
+     * Pawns have no symbol in Standard Algebraic Notation.
+     *
+     * @param kind - The piece kind to get the symbol of.
+     * @return The symbol of the piece kind, or None for pawns.
+     */
+    pub fn symbol(&self, kind: PieceKind) -> Option<char> {
+        let i = match kind {
+            PieceKind::King => 0,
+            PieceKind::Queen => 1,
+            PieceKind::Rook => 2,
+            PieceKind::Bishop => 3,
+            PieceKind::Knight => 4,
+            PieceKind::Pawn => return None,
+        };
+        let symbols = match self {
+            PieceNotation::Letters => ['K', 'Q', 'R', 'B', 'N'],
+            PieceNotation::Figurine => ['♔', '♕', '♖', '♗', '♘'],
+            PieceNotation::Localized(letters) => *letters,
+        };
+        Some(symbols[i])
+    }
 }
 
-// Function to get a move from an AI
 /**
- * AI Move Index
+ * Renders a move in Standard Algebraic Notation (SAN).
  *
- * This function determines the move to be made by the AI player and returns the indices of the
- * source and destination squares as a tuple of four `u8` values.
+ * @param board - The board the move is played from, with up to date legal moves.
+ * @param m - The legal move to render.
+ * @return The move in Standard Algebraic Notation, such as "Nbd7", "exd6", "e8=Q+" or "O-O-O#".
+ */
+pub fn to_san(board: &Board, m: &Move) -> String {
+    to_san_with(board, m, PieceNotation::Letters)
+}
+
+/**
+ * Renders a move in Standard Algebraic Notation (SAN) with the given piece notation.
+ *
+ * Piece moves are disambiguated by file if that is enough to tell them apart from the other legal moves of the
+ * same piece kind to the same square, otherwise by rank, and by both if neither is enough. Pawn captures always
+ * start with the file the pawn moves from. A move that gives check is suffixed with "+" and a move that gives
+ * checkmate with "#".
  *
- * @param _player - The player for which the AI move is being calculated.
- * @return A tuple containing the indices of the source and destination squares in the form
- *         (src_row, src_col, dest_row, dest_col).
+ * @param board - The board the move is played from, with up to date legal moves.
+ * @param m - The legal move to render.
+ * @param notation - How the piece symbols are written.
+ * @return The move in Standard Algebraic Notation.
  */
-pub fn ai_mv_idx(_player: &Player) -> (u8, u8, u8, u8) {
-    // ... get a move from the AI ...
-    (0, 1, 2, 2)
+pub fn to_san_with(board: &Board, m: &Move, notation: PieceNotation) -> String {
+    let mut san = String::new();
+    match m.move_type {
+        MoveType::Castle(CastleType::KingSide) => san.push_str("O-O"),
+        MoveType::Castle(CastleType::QueenSide) => san.push_str("O-O-O"),
+        _ => {
+            let from = algebraic_from_pos(m.from);
+            match notation.symbol(m.from_piece.kind) {
+                Some(symbol) => {
+                    san.push(symbol);
+                    let rivals: Vec<&Move> = board
+                        .board_info
+                        .valid_moves
+                        .iter()
+                        .filter(|other| {
+                            other.from_piece.kind == m.from_piece.kind
+                                && other.to == m.to
+                                && other.from != m.from
+                        })
+                        .collect();
+                    if !rivals.is_empty() {
+                        if rivals.iter().all(|other| other.from.0 != m.from.0) {
+                            san.push_str(&from[..1]);
+                        } else if rivals.iter().all(|other| other.from.1 != m.from.1) {
+                            san.push_str(&from[1..]);
+                        } else {
+                            san.push_str(&from);
+                        }
+                    }
+                }
+                None if m.is_capture() => san.push_str(&from[..1]),
+                None => (),
+            }
+            if m.is_capture() {
+                san.push('x');
+            }
+            san.push_str(&algebraic_from_pos(m.to));
+            if let Some(kind) = m.promotion() {
+                san.push('=');
+                san.extend(notation.symbol(kind));
+            }
+        }
+    }
+
+    let mut after = board.clone();
+    after.make_move(m.clone());
+    after.update();
+    if after.board_info.is_in_check(m.color.other()) {
+        san.push(if after.board_info.valid_moves.is_empty() {
+            '#'
+        } else {
+            '+'
+        });
+    }
+    san
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::piece::PieceKind;
+    use crate::board::Board;
+    use crate::game::player::{parse_move, to_san, to_san_with, PieceNotation, SanError, Timer};
+    use crate::rules::r#move::{CastleType, Move, MoveType};
+    use std::time::Duration;
+
+    #[test]
+    /**
+     * Tests that moves are counted down from a clock in use, which gains the increment, and that a clock that is
+     * not in use is left untouched.
+     */
+    fn test_timer() {
+        let mut timer = Timer::new();
+        assert!(!timer.is_timed());
+        assert!(timer.spend(Duration::from_secs(100)));
+        assert_eq!(timer.time, Duration::ZERO);
+
+        timer.time = Duration::from_secs(60);
+        timer.increment = Duration::from_secs(2);
+        assert!(timer.spend(Duration::from_secs(10)));
+        assert_eq!(timer.time, Duration::from_secs(52));
+        assert!(!timer.spend(Duration::from_secs(52)));
+        assert_eq!(timer.time, Duration::ZERO);
+    }
+
+    #[test]
+    /**
+     * Tests that piece moves, pawn moves and captures are resolved to the right legal move.
+     */
+    fn test_parse_move() {
+        let board = Board::new_standard();
+        let m = parse_move(&board, "e4").unwrap();
+        assert_eq!((m.from, m.to), ((4, 1), (4, 3)));
+        let m = parse_move(&board, "Nf3").unwrap();
+        assert_eq!((m.from, m.to), ((6, 0), (5, 2)));
+
+        let board = Board::new_from_fen("4k3/8/8/4p3/8/2B5/8/4K3 w - - 0 1").unwrap();
+        let m = parse_move(&board, "Bxe5").unwrap();
+        assert_eq!(
+            (m.from, m.to, m.move_type),
+            ((2, 2), (4, 4), MoveType::Capture)
+        );
+
+        let board = Board::new_from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let m = parse_move(&board, "exd6").unwrap();
+        assert_eq!(
+            (m.from, m.to, m.move_type),
+            ((4, 4), (3, 5), MoveType::EnPassant)
+        );
+    }
+
+    #[test]
+    /**
+     * Tests castling, promotions and disambiguated moves.
+     */
+    fn test_parse_special_moves() {
+        let board = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let m = parse_move(&board, "O-O-O").unwrap();
+        assert_eq!(m.move_type, MoveType::Castle(CastleType::QueenSide));
+        let m = parse_move(&board, "0-0").unwrap();
+        assert_eq!(m.move_type, MoveType::Castle(CastleType::KingSide));
+
+        let board = Board::new_from_fen("k7/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let m = parse_move(&board, "e8=Q+").unwrap();
+        assert_eq!(m.move_type, MoveType::Promotion(PieceKind::Queen));
+        let m = parse_move(&board, "e8N").unwrap();
+        assert_eq!(m.move_type, MoveType::Promotion(PieceKind::Knight));
+
+        let board = Board::new_from_fen("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let m = parse_move(&board, "Nbd7").unwrap();
+        assert_eq!((m.from, m.to), ((1, 7), (3, 6)));
+        let m = parse_move(&board, "Nfd7").unwrap();
+        assert_eq!(m.from, (5, 5));
+
+        let board = Board::new_from_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap();
+        let m = parse_move(&board, "R1a3").unwrap();
+        assert_eq!((m.from, m.to), ((0, 0), (0, 2)));
+        let m = parse_move(&board, "R5a3").unwrap();
+        assert_eq!(m.from, (0, 4));
+    }
+
+    #[test]
+    /**
+     * Tests that ambiguous, illegal and malformed moves are rejected with a descriptive error.
+     */
+    fn test_parse_move_errors() {
+        let board = Board::new_standard();
+        assert_eq!(parse_move(&board, "  "), Err(SanError::Empty));
+        assert_eq!(
+            parse_move(&board, "e5"),
+            Err(SanError::Illegal("e5".to_string()))
+        );
+        assert_eq!(
+            parse_move(&board, "Nxf3"),
+            Err(SanError::Illegal("Nxf3".to_string()))
+        );
+        assert_eq!(
+            parse_move(&board, "O-O"),
+            Err(SanError::Illegal("O-O".to_string()))
+        );
+        for san in ["Zf3", "e9", "N", "xe4", "Nf3=Q", "e2e4x", "+"] {
+            assert_eq!(
+                parse_move(&board, san),
+                Err(SanError::Malformed(san.to_string()))
+            );
+        }
+
+        let board = Board::new_from_fen("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let error = parse_move(&board, "Nd7").unwrap_err();
+        assert_eq!(
+            error,
+            SanError::Ambiguous("Nd7".to_string(), vec![(5, 5), (1, 7)])
+        );
+        assert_eq!(
+            error.to_string(),
+            "'Nd7' is ambiguous, it could be played from f6 or b8"
+        );
+
+        let board = Board::new_from_fen("k7/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            parse_move(&board, "e8"),
+            Err(SanError::MissingPromotion("e8".to_string()))
+        );
+    }
+
+    /**
+     * Finds the legal move from one square to another.
+     *
+     * @param board - The board to find the move on.
+     * @param from - The square the piece moves from.
+     * @param to - The square the piece moves to.
+     * @return The first legal move between the squares.
+     */
+    fn find_move(board: &Board, from: (u8, u8), to: (u8, u8)) -> Move {
+        board
+            .board_info
+            .valid_moves
+            .iter()
+            .find(|m| m.from == from && m.to == to)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    /**
+     * Tests SAN output for captures, promotions, castling, disambiguation and check suffixes.
+     */
+    fn test_to_san() {
+        let board = Board::new_standard();
+        assert_eq!(to_san(&board, &find_move(&board, (4, 1), (4, 3))), "e4");
+        assert_eq!(to_san(&board, &find_move(&board, (6, 0), (5, 2))), "Nf3");
+
+        let board = Board::new_from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        assert_eq!(to_san(&board, &find_move(&board, (4, 4), (3, 5))), "exd6");
+
+        let board = Board::new_from_fen("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(to_san(&board, &find_move(&board, (1, 7), (3, 6))), "Nbd7");
+        let board = Board::new_from_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, &find_move(&board, (0, 0), (0, 2))), "R1a3");
+        let board = Board::new_from_fen("1k6/8/8/8/4Q2Q/8/K7/7Q w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, &find_move(&board, (7, 3), (4, 0))), "Qh4e1");
+        assert_eq!(to_san(&board, &find_move(&board, (4, 3), (4, 0))), "Qee1");
+        assert_eq!(to_san(&board, &find_move(&board, (7, 0), (4, 0))), "Q1e1");
+
+        let board = Board::new_from_fen("k7/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = board
+            .board_info
+            .valid_moves
+            .iter()
+            .find(|m| m.move_type == MoveType::Promotion(PieceKind::Queen))
+            .unwrap();
+        assert_eq!(to_san(&board, promotion), "e8=Q+");
+
+        let board = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(to_san(&board, &find_move(&board, (4, 0), (6, 0))), "O-O");
+        assert_eq!(to_san(&board, &find_move(&board, (4, 0), (2, 0))), "O-O-O");
+
+        let board =
+            Board::new_from_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2")
+                .unwrap();
+        assert_eq!(to_san(&board, &find_move(&board, (3, 7), (7, 3))), "Qh4#");
+    }
+
+    #[test]
+    /**
+     * Tests figurine and localized piece symbols.
+     */
+    fn test_to_san_notation() {
+        let board = Board::new_standard();
+        let m = find_move(&board, (6, 0), (5, 2));
+        assert_eq!(to_san_with(&board, &m, PieceNotation::Figurine), "♘f3");
+        let german = PieceNotation::Localized(['K', 'D', 'T', 'L', 'S']);
+        assert_eq!(to_san_with(&board, &m, german), "Sf3");
+
+        let board = Board::new_from_fen("k7/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = board
+            .board_info
+            .valid_moves
+            .iter()
+            .find(|m| m.move_type == MoveType::Promotion(PieceKind::Queen))
+            .unwrap();
+        assert_eq!(to_san_with(&board, promotion, german), "e8=D+");
+    }
+
+    #[test]
+    /**
+     * Tests that every legal move written in SAN parses back to the same move.
+     */
+    fn test_san_round_trip() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "1k6/8/8/8/4Q2Q/8/K7/7Q w - - 0 1",
+        ];
+        for fen in fens {
+            let board = Board::new_from_fen(fen).unwrap();
+            for m in &board.board_info.valid_moves {
+                let san = to_san(&board, m);
+                assert_eq!(
+                    parse_move(&board, &san).as_ref(),
+                    Ok(m),
+                    "{} in {}",
+                    san,
+                    fen
+                );
+            }
+        }
+    }
 }
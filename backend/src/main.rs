@@ -1,15 +1,46 @@
 pub mod board;
+pub mod eval;
 pub mod game;
+pub mod protocol;
 pub mod rules;
+pub mod search;
 
 use crate::game::*;
 
 /**
  * The main entry point of the program.
  *
- * This function initializes a standard game and starts playing.
+ * Run with the `uci` argument, the program speaks the Universal Chess Interface on the standard input and output,
+ * and with the `xboard` argument the Chess Engine Communication Protocol. The `bench` argument, optionally followed
+ * by a depth, prints how many positions the search visits with and without move ordering. The `perft` argument,
+ * followed by a depth and optionally a FEN string, counts the positions of the legal move tree per root move.
+ * Otherwise this function initializes a standard game and starts playing.
  */
 fn main() {
-    let game = Game::new_standard();
-    play(game);
+    match std::env::args().nth(1).as_deref() {
+        Some("uci") => protocol::uci::run(),
+        Some("xboard") => protocol::xboard::run(),
+        Some("bench") => {
+            let depth = std::env::args().nth(2).and_then(|depth| depth.parse().ok());
+            search::bench::run(depth.unwrap_or(search::bench::DEFAULT_BENCH_DEPTH));
+        }
+        Some("perft") => {
+            let args: Vec<String> = std::env::args().skip(2).collect();
+            let Some(depth) = args.first().and_then(|depth| depth.parse().ok()) else {
+                eprintln!("usage: perft <depth> [fen]");
+                return;
+            };
+            let fen = match args[1..].join(" ") {
+                fen if fen.is_empty() => board::START_FEN.to_string(),
+                fen => fen,
+            };
+            if let Err(error) = rules::perft::run(&fen, depth) {
+                eprintln!("invalid FEN: {}", error);
+            }
+        }
+        _ => {
+            let game = Game::new_standard();
+            play(game);
+        }
+    }
 }
@@ -0,0 +1,19 @@
+pub mod uci;
+pub mod xboard;
+
+use std::io::Write;
+use std::sync::Mutex;
+
+/**
+ * Writes a line of protocol output and flushes it right away.
+ *
+ * Engine protocols are line based and GUIs wait for each reply, so every line is flushed as soon as it is written.
+ *
+ * @param out - The shared output to write to.
+ * @param line - The line to write, without the trailing newline.
+ */
+pub fn send<W: Write>(out: &Mutex<W>, line: &str) {
+    let mut out = out.lock().unwrap();
+    let _ = writeln!(out, "{}", line);
+    let _ = out.flush();
+}
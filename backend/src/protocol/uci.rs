@@ -0,0 +1,545 @@
+use crate::board::{Board, START_FEN};
+use crate::game::player::{from_idx, Brain};
+use crate::protocol::send;
+use crate::rules::r#move::Move;
+use crate::search::tt::{TranspositionTable, DEFAULT_HASH_MB};
+use crate::search::{SearchLimits, SearchResult};
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const MAX_HASH_MB: usize = 4096; // Largest transposition table the Hash option allows, in megabytes
+
+// Arguments of the `go` command, which also end the list of moves after `searchmoves`
+const GO_ARGUMENTS: [&str; 12] = [
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+];
+
+// Struct to hold the state of an engine speaking the Universal Chess Interface
+pub struct Uci<W: Write + Send + 'static> {
+    pub board: Board,                   // Position set by the last `position` command
+    pub move_overhead: Duration,        // Time kept in reserve for every move
+    out: Arc<Mutex<W>>,                 // Where replies are written to
+    stop: Arc<AtomicBool>,              // Set to end the running search
+    search: Option<JoinHandle<()>>,     // The running search, if any
+    tt: Arc<Mutex<TranspositionTable>>, // Transposition table shared by all searches of a game
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    /**
+     * Creates a new UCI engine writing its replies to the given output.
+     *
+     * @param out - The shared output to write replies to.
+     * @return A new UCI engine set up with the standard starting position.
+     */
+    pub fn new(out: Arc<Mutex<W>>) -> Self {
+        Self {
+            board: Board::new_standard(),
+            move_overhead: Duration::from_millis(10),
+            out,
+            stop: Arc::new(AtomicBool::new(false)),
+            search: None,
+            tt: Arc::new(Mutex::new(TranspositionTable::default())),
+        }
+    }
+
+    /**
+     * Handles a single command sent by the GUI.
+     *
+     * Unknown commands and malformed arguments are reported with an `info string` line and otherwise ignored.
+     *
+     * @param line - The command line sent by the GUI.
+     * @return false if the engine should quit, true otherwise.
+     */
+    pub fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+        match command {
+            "uci" => {
+                self.send(&format!("id name Rust-Chess {}", env!("CARGO_PKG_VERSION")));
+                self.send("id author the Rust-Chess developers");
+                self.send("option name Move Overhead type spin default 10 min 0 max 5000");
+                self.send(&format!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MB, MAX_HASH_MB
+                ));
+                self.send("uciok");
+            }
+            "isready" => self.send("readyok"),
+            "ucinewgame" => {
+                self.stop_search();
+                self.board = Board::new_standard();
+                self.tt.lock().unwrap().clear();
+            }
+            "position" => {
+                self.stop_search();
+                if let Err(error) = self.set_position(args) {
+                    self.send(&format!("info string {}", error));
+                }
+            }
+            "setoption" => self.set_option(args),
+            "go" => {
+                self.stop_search();
+                self.start_search(parse_go(args));
+            }
+            "stop" => self.stop_search(),
+            "quit" => {
+                self.stop_search();
+                return false;
+            }
+            "debug" | "register" | "ponderhit" => (),
+            _ => self.send(&format!("info string unknown command '{}'", command)),
+        }
+        true
+    }
+
+    /**
+     * Writes a line of output to the GUI.
+     *
+     * @param line - The line to write.
+     */
+    fn send(&self, line: &str) {
+        send(&self.out, line);
+    }
+
+    /**
+     * Sets up the position given by a `position` command.
+     *
+     * The position is either `startpos` or `fen` followed by a FEN string, optionally followed by `moves` and
+     * the moves played from it in UCI coordinate notation. On error the position is left unchanged.
+     *
+     * @param args - The arguments of the `position` command.
+     * @return Ok if the position was set up, or a description of what was wrong.
+     */
+    fn set_position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_at = args.iter().position(|&arg| arg == "moves");
+        let (setup, moves) = match moves_at {
+            Some(i) => (&args[..i], &args[i + 1..]),
+            None => (args, &[][..]),
+        };
+
+        let mut board = match setup.split_first() {
+            Some((&"startpos", [])) => Board::new_from_fen(START_FEN).unwrap(),
+            Some((&"fen", fen)) => {
+                Board::new_from_fen(&fen.join(" ")).map_err(|e| e.to_string())?
+            }
+            _ => {
+                return Err(String::from(
+                    "expected 'position startpos' or 'position fen <fen>'",
+                ))
+            }
+        };
+        for uci in moves {
+            let m = Move::from_uci(&board, uci).map_err(|e| e.to_string())?;
+            board.make_move(m);
+            board.update();
+        }
+
+        self.board = board;
+        Ok(())
+    }
+
+    /**
+     * Applies a `setoption name <name> value <value>` command.
+     *
+     * @param args - The arguments of the `setoption` command.
+     */
+    fn set_option(&mut self, args: &[&str]) {
+        let value_at = args.iter().position(|&arg| arg == "value");
+        let name = args[..value_at.unwrap_or(args.len())]
+            .iter()
+            .skip_while(|&&arg| arg == "name")
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let value = value_at
+            .map(|i| args[i + 1..].join(" "))
+            .unwrap_or_default();
+
+        match name.to_lowercase().as_str() {
+            "move overhead" => match value.parse() {
+                Ok(ms) if ms <= 5000 => self.move_overhead = Duration::from_millis(ms),
+                _ => self.send(&format!("info string invalid Move Overhead '{}'", value)),
+            },
+            "hash" => match value.parse() {
+                Ok(mb) if (1..=MAX_HASH_MB).contains(&mb) => {
+                    self.stop_search();
+                    *self.tt.lock().unwrap() = TranspositionTable::new(mb);
+                }
+                _ => self.send(&format!("info string invalid Hash '{}'", value)),
+            },
+            _ => self.send(&format!("info string unknown option '{}'", name)),
+        }
+    }
+
+    /**
+     * Starts searching the current position in the background.
+     *
+     * Every completed iteration is reported with an `info` line, and the best move with a `bestmove` line once
+     * the search ends. In infinite mode the best move is held back until the search is stopped.
+     *
+     * @param limits - The limits the search has to respect.
+     */
+    fn start_search(&mut self, mut limits: SearchLimits) {
+        self.stop.store(false, Ordering::Relaxed);
+        limits.move_overhead = self.move_overhead;
+        let board = self.board.clone();
+        let to_move = from_idx(board.board_info.turn);
+        let stop = self.stop.clone();
+        let out = self.out.clone();
+        let tt = self.tt.clone();
+
+        self.search = Some(thread::spawn(move || {
+            let started = Instant::now();
+            let mut brain = Brain::with_table(board.clone(), to_move, tt);
+            brain.stop = stop.clone();
+            let info_out = out.clone();
+            brain.reporter = Some(Arc::new(move |result: &SearchResult| {
+                send(&info_out, &info_line(result, started.elapsed()));
+            }));
+
+            let result = brain.best_move(&board, &limits);
+            while limits.infinite && !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let best = result
+                .best_move
+                .map_or(String::from("0000"), |m| m.to_uci());
+            send(&out, &format!("bestmove {}", best));
+        }));
+    }
+
+    /**
+     * Stops the running search, if any, and waits for it to send its best move.
+     */
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            let _ = search.join();
+        }
+    }
+}
+
+/**
+ * Parses the arguments of a `go` command into search limits.
+ *
+ * Supported arguments are `depth`, `movetime`, `wtime`, `btime`, `winc`, `binc`, `movestogo` and `infinite`.
+ * Times are given in milliseconds, and negative times, which some GUIs send once a clock has run out, count as
+ * zero. `ponder`, `nodes`, `mate` and `searchmoves` with their values are accepted but ignored, and so are
+ * unknown arguments and values that are not numbers, so that every `go` is answered with a best move.
+ *
+ * @param args - The arguments of the `go` command.
+ * @return The search limits.
+ */
+pub fn parse_go(args: &[&str]) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut args = args.iter().peekable();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "infinite" => limits.infinite = true,
+            "searchmoves" => while args.next_if(|arg| !GO_ARGUMENTS.contains(arg)).is_some() {},
+            _ => {
+                let Some(number) = args.next_if(|value| value.parse::<i64>().is_ok()) else {
+                    continue;
+                };
+                let number = number.parse::<i64>().unwrap_or(0).max(0) as u64;
+                let millis = Duration::from_millis(number);
+                match arg {
+                    "depth" => limits.depth = Some(number as u32),
+                    "movetime" => limits.movetime = Some(millis),
+                    "wtime" => limits.wtime = Some(millis),
+                    "btime" => limits.btime = Some(millis),
+                    "winc" => limits.winc = millis,
+                    "binc" => limits.binc = millis,
+                    "movestogo" => limits.movestogo = Some(number as u32),
+                    _ => (),
+                }
+            }
+        }
+    }
+    limits
+}
+
+/**
+ * Formats a search result as a UCI `info` line.
+ *
+ * @param result - The result of a completed search iteration.
+ * @param elapsed - The time spent searching so far.
+ * @return The `info` line.
+ */
+pub fn info_line(result: &SearchResult, elapsed: Duration) -> String {
+    let millis = elapsed.as_millis();
+    let nps = result.nodes as u128 * 1000 / millis.max(1);
+    let pv: Vec<String> = result.pv.iter().map(|m| m.to_uci()).collect();
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    format!(
+        "info depth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        nps,
+        result.hashfull,
+        millis,
+        pv.join(" ")
+    )
+}
+
+/**
+ * Runs the engine in UCI mode, reading commands from the standard input until `quit` or the end of the input.
+ */
+pub fn run() {
+    let mut uci = Uci::new(Arc::new(Mutex::new(std::io::stdout())));
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !uci.handle(&line) {
+            return;
+        }
+    }
+    uci.handle("quit");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::uci::{parse_go, Uci};
+    use crate::search::tt::TranspositionTable;
+    use crate::search::SearchLimits;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /**
+     * Creates a UCI engine writing into a buffer that the test can read.
+     *
+     * @return The engine and its output buffer.
+     */
+    fn engine() -> (Uci<Vec<u8>>, Arc<Mutex<Vec<u8>>>) {
+        let out = Arc::new(Mutex::new(Vec::new()));
+        (Uci::new(out.clone()), out)
+    }
+
+    /**
+     * Takes the lines written to the buffer so far.
+     *
+     * @param out - The output buffer of the engine.
+     * @return The lines written since the last call.
+     */
+    fn take_lines(out: &Mutex<Vec<u8>>) -> Vec<String> {
+        let bytes = std::mem::take(&mut *out.lock().unwrap());
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    /**
+     * Waits until the engine has sent its best move and takes the lines written so far.
+     *
+     * @param out - The output buffer of the engine.
+     * @return The lines written since the last call, ending with the `bestmove` line.
+     */
+    fn wait_for_bestmove(out: &Mutex<Vec<u8>>) -> Vec<String> {
+        let mut lines = Vec::new();
+        for _ in 0..5000 {
+            lines.extend(take_lines(out));
+            if lines.iter().any(|line| line.starts_with("bestmove")) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        lines
+    }
+
+    #[test]
+    /**
+     * Tests the handshake, readiness check and quitting.
+     */
+    fn test_uci_handshake() {
+        let (mut uci, out) = engine();
+        assert!(uci.handle("uci"));
+        let lines = take_lines(&out);
+        assert!(lines[0].starts_with("id name Rust-Chess"));
+        assert_eq!(lines.last().unwrap(), "uciok");
+
+        assert!(uci.handle("isready"));
+        assert_eq!(take_lines(&out), ["readyok"]);
+        assert!(uci.handle("   "));
+        assert!(uci.handle("xyzzy"));
+        assert_eq!(take_lines(&out), ["info string unknown command 'xyzzy'"]);
+        assert!(!uci.handle("quit"));
+    }
+
+    #[test]
+    /**
+     * Tests setting up positions from the start position and from FEN strings, with and without moves.
+     */
+    fn test_uci_position() {
+        let (mut uci, out) = engine();
+        uci.handle("position startpos moves e2e4 e7e5 g1f3");
+        assert_eq!(
+            uci.board.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+
+        uci.handle("position fen r3k3/1P6/8/8/8/8/8/R3K2R w Kq - 0 1 moves e1g1 a8a1 b7b8q");
+        assert_eq!(uci.board.to_fen(), "1Q2k3/8/8/8/8/8/8/r4RK1 b - - 0 2");
+        assert!(take_lines(&out).is_empty());
+
+        // An illegal move leaves the previous position in place
+        uci.handle("position startpos moves e2e5");
+        assert_eq!(uci.board.to_fen(), "1Q2k3/8/8/8/8/8/8/r4RK1 b - - 0 2");
+        assert_eq!(
+            take_lines(&out),
+            ["info string 'e2e5' is not a legal move in this position"]
+        );
+
+        // A position with more pieces than a side can have is rejected
+        uci.handle("position fen QQQQQQQk/Q6Q/Q6Q/Q6Q/Q6Q/Q6Q/Q6Q/KQQQQQQQ w - - 0 1");
+        assert_eq!(uci.board.to_fen(), "1Q2k3/8/8/8/8/8/8/r4RK1 b - - 0 2");
+        assert_eq!(
+            take_lines(&out),
+            ["info string White has more than 16 pieces"]
+        );
+
+        uci.handle("ucinewgame");
+        assert_eq!(uci.board.to_fen(), crate::board::START_FEN);
+    }
+
+    #[test]
+    /**
+     * Tests that searches report their progress and best move, and that infinite searches wait for `stop`.
+     */
+    fn test_uci_go() {
+        let (mut uci, out) = engine();
+        uci.handle("position fen 4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1");
+        uci.handle("go depth 1");
+        let lines = wait_for_bestmove(&out);
+        let info = lines
+            .iter()
+            .find(|line| line.starts_with("info depth 1 score cp "))
+            .unwrap();
+        assert!(!info.starts_with("info depth 1 score cp -"));
+        assert_eq!(lines.last().unwrap(), "bestmove e4d5");
+
+        uci.handle("go infinite");
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!take_lines(&out)
+            .iter()
+            .any(|line| line.starts_with("bestmove")));
+        uci.handle("stop");
+        assert_eq!(take_lines(&out).last().unwrap(), "bestmove e4d5");
+
+        // Every go is answered, whatever its arguments
+        uci.handle("go ponder wtime 1000 btime 1000");
+        assert!(wait_for_bestmove(&out)
+            .last()
+            .unwrap()
+            .starts_with("bestmove "));
+        uci.handle("go wtime -50 btime 1000");
+        assert!(wait_for_bestmove(&out)
+            .last()
+            .unwrap()
+            .starts_with("bestmove "));
+
+        // Checkmated positions have no move to play
+        uci.handle("position fen rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        uci.handle("go movetime 10");
+        uci.handle("quit");
+        assert_eq!(take_lines(&out).last().unwrap(), "bestmove 0000");
+    }
+
+    #[test]
+    /**
+     * Tests parsing of `go` arguments and options.
+     */
+    fn test_uci_go_arguments() {
+        let limits = parse_go(&[
+            "wtime",
+            "60000",
+            "btime",
+            "50000",
+            "winc",
+            "1000",
+            "movestogo",
+            "20",
+        ]);
+        assert_eq!(limits.wtime, Some(Duration::from_secs(60)));
+        assert_eq!(limits.btime, Some(Duration::from_secs(50)));
+        assert_eq!(limits.winc, Duration::from_secs(1));
+        assert_eq!(limits.movestogo, Some(20));
+
+        // Unsupported arguments and invalid values are skipped
+        assert_eq!(parse_go(&["depth"]), SearchLimits::default());
+        assert_eq!(parse_go(&["depth", "x"]), SearchLimits::default());
+        assert_eq!(
+            parse_go(&["nodes", "100", "mate", "3"]),
+            SearchLimits::default()
+        );
+        let limits = parse_go(&["ponder", "wtime", "1000", "btime", "2000", "binc", "x"]);
+        assert_eq!(limits.wtime, Some(Duration::from_secs(1)));
+        assert_eq!(limits.btime, Some(Duration::from_secs(2)));
+        assert_eq!(limits.binc, Duration::ZERO);
+        let limits = parse_go(&["searchmoves", "e2e4", "d2d4", "depth", "3", "xyzzy"]);
+        assert_eq!(limits.depth, Some(3));
+
+        // Negative times count as zero
+        let limits = parse_go(&["wtime", "-50", "btime", "1000"]);
+        assert_eq!(limits.wtime, Some(Duration::ZERO));
+        assert_eq!(limits.btime, Some(Duration::from_secs(1)));
+
+        let (mut uci, out) = engine();
+        uci.handle("setoption name Move Overhead value 50");
+        assert_eq!(uci.move_overhead, Duration::from_millis(50));
+        uci.handle("setoption name Hash Size value 64");
+        assert_eq!(take_lines(&out), ["info string unknown option 'Hash Size'"]);
+
+        uci.handle("setoption name Hash value 2");
+        assert_eq!(
+            uci.tt.lock().unwrap().capacity(),
+            TranspositionTable::new(2).capacity()
+        );
+        uci.handle("setoption name Hash value 0");
+        assert_eq!(take_lines(&out), ["info string invalid Hash '0'"]);
+    }
+
+    #[test]
+    /**
+     * Tests that searches fill the transposition table, report how full it is, and that a new game clears it.
+     */
+    fn test_uci_hash() {
+        let (mut uci, out) = engine();
+        uci.handle("uci");
+        assert!(take_lines(&out).contains(&String::from(
+            "option name Hash type spin default 16 min 1 max 4096"
+        )));
+
+        uci.handle("setoption name Hash value 1");
+        uci.handle("position startpos moves e2e4");
+        uci.handle("go depth 3");
+        let lines = wait_for_bestmove(&out);
+        assert!(lines.iter().any(|line| line.contains(" hashfull ")));
+        assert!(uci.tt.lock().unwrap().hashfull() > 0);
+
+        uci.handle("ucinewgame");
+        assert_eq!(uci.tt.lock().unwrap().hashfull(), 0);
+    }
+}
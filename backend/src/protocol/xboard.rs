@@ -0,0 +1,686 @@
+use crate::board::Board;
+use crate::game::player::{from_idx, parse_move, to_san, Brain, Color, PlayerKind};
+use crate::game::{apply_legal_move, undo_move, Game};
+use crate::protocol::send;
+use crate::rules::r#move::Move;
+use crate::search::tt::TranspositionTable;
+use crate::search::{SearchLimits, SearchResult};
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Enum to represent the events an xboard engine reacts to
+#[derive(Clone, Debug)]
+pub enum Event {
+    Command(String),             // A command line sent by the GUI
+    Searched(u64, SearchResult), // The result of the search with the given id
+}
+
+// Struct to hold the state of an engine speaking the Chess Engine Communication Protocol
+pub struct Xboard<W: Write + Send + 'static> {
+    pub game: Game,                      // The game being played
+    pub force: bool,                     // In force mode the engine plays neither side
+    pub engine_color: Color,             // The side the engine plays when not in force mode
+    pub post: bool,                      // Whether thinking output is sent
+    pub depth: Option<u32>,              // Depth limit set by `sd`
+    pub move_time: Option<Duration>,     // Fixed time per move set by `st`
+    pub moves_per_control: Option<u32>,  // Moves per control set by `level`, None for sudden death
+    pub increment: Duration,             // Increment per move set by `level`
+    pub engine_time: Option<Duration>,   // Engine's time left, set by `time`
+    pub opponent_time: Option<Duration>, // Opponent's time left, set by `otim`
+    out: Arc<Mutex<W>>,                  // Where replies are written to
+    events: Sender<Event>,               // Where finished searches are reported
+    stop: Arc<AtomicBool>,               // Set to end the running search
+    search_id: u64,                      // Id of the running search, or of the last one
+    searching: bool,                     // Whether a search is running
+    search: Option<JoinHandle<()>>,      // The thread of the running or last search
+    tt: Arc<Mutex<TranspositionTable>>,  // Transposition table shared by all searches of a game
+}
+
+impl<W: Write + Send + 'static> Xboard<W> {
+    /**
+     * Creates a new xboard engine writing its replies to the given output.
+     *
+     * @param out - The shared output to write replies to.
+     * @param events - Where the results of finished searches are sent, to be passed back to `handle`.
+     * @return A new xboard engine playing Black in a new game.
+     */
+    pub fn new(out: Arc<Mutex<W>>, events: Sender<Event>) -> Self {
+        Self {
+            game: Game::new_standard(),
+            force: false,
+            engine_color: Color::Black,
+            post: false,
+            depth: None,
+            move_time: None,
+            moves_per_control: None,
+            increment: Duration::ZERO,
+            engine_time: None,
+            opponent_time: None,
+            out,
+            events,
+            stop: Arc::new(AtomicBool::new(false)),
+            search_id: 0,
+            searching: false,
+            search: None,
+            tt: Arc::new(Mutex::new(TranspositionTable::default())),
+        }
+    }
+
+    /**
+     * Handles a command from the GUI or the result of a finished search.
+     *
+     * @param event - The event to handle.
+     * @return false if the engine should quit, true otherwise.
+     */
+    pub fn handle(&mut self, event: Event) -> bool {
+        match event {
+            Event::Command(line) => self.handle_command(&line),
+            Event::Searched(id, result) => {
+                if self.searching && id == self.search_id {
+                    self.searching = false;
+                    self.play_engine_move(result);
+                }
+                true
+            }
+        }
+    }
+
+    /**
+     * Handles a single command sent by the GUI.
+     *
+     * @param line - The command line sent by the GUI.
+     * @return false if the engine should quit, true otherwise.
+     */
+    fn handle_command(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+        match command {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "easy" | "hard" | "hint"
+            | "bk" | "computer" | "name" | "draw" | "ics" | "." => (),
+            "protover" => self.send(&format!(
+                "feature myname=\"Rust-Chess {}\" ping=1 setboard=1 usermove=1 playother=1 \
+                 san=0 time=1 draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 memory=1 done=1",
+                env!("CARGO_PKG_VERSION")
+            )),
+            "new" => {
+                self.cancel_search();
+                self.game = Game::new_standard();
+                self.force = false;
+                self.engine_color = Color::Black;
+                self.depth = None;
+                self.tt.lock().unwrap().clear();
+            }
+            "force" => {
+                self.cancel_search();
+                self.force = true;
+            }
+            "go" => {
+                self.cancel_search();
+                self.force = false;
+                self.engine_color = self.side_to_move();
+                self.think();
+            }
+            "playother" => {
+                self.cancel_search();
+                self.force = false;
+                self.engine_color = self.side_to_move().other();
+            }
+            "usermove" => self.user_move(args),
+            "setboard" => {
+                self.cancel_search();
+                match Game::new_from_fen(args) {
+                    Ok(game) => self.game = game,
+                    Err(error) => self.send(&format!("tellusererror Illegal position: {}", error)),
+                }
+            }
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "level" => match parse_level(args) {
+                Some((moves, _base, increment)) => {
+                    self.moves_per_control = moves;
+                    self.increment = increment;
+                    self.move_time = None;
+                }
+                None => self.send(&format!("Error (bad level): {}", args)),
+            },
+            "st" => match args.parse::<u64>() {
+                Ok(seconds) => self.move_time = Some(Duration::from_secs(seconds)),
+                Err(_) => self.send(&format!("Error (bad time): {}", args)),
+            },
+            "sd" => match args.parse() {
+                Ok(depth) => self.depth = Some(depth),
+                Err(_) => self.send(&format!("Error (bad depth): {}", args)),
+            },
+            "time" | "otim" => match args.parse::<u64>() {
+                Ok(centiseconds) => {
+                    let time = Some(Duration::from_millis(centiseconds * 10));
+                    if command == "time" {
+                        self.engine_time = time;
+                    } else {
+                        self.opponent_time = time;
+                    }
+                }
+                Err(_) => self.send(&format!("Error (bad time): {}", args)),
+            },
+            "memory" => match args.parse::<usize>() {
+                Ok(megabytes) if megabytes > 0 => {
+                    self.cancel_search();
+                    *self.tt.lock().unwrap() = TranspositionTable::new(megabytes);
+                }
+                _ => self.send(&format!("Error (bad memory): {}", args)),
+            },
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "ping" => self.send(&format!("pong {}", args)),
+            "result" => {
+                self.cancel_search();
+                self.force = true;
+            }
+            "?" => self.stop.store(true, Ordering::Relaxed),
+            "quit" => {
+                self.cancel_search();
+                return false;
+            }
+            // Without the usermove feature moves arrive on their own
+            _ if Move::from_uci(&self.game.board, line).is_ok() => self.user_move(line),
+            _ => self.send(&format!("Error (unknown command): {}", command)),
+        }
+        true
+    }
+
+    /**
+     * Writes a line of output to the GUI.
+     *
+     * @param line - The line to write.
+     */
+    fn send(&self, line: &str) {
+        send(&self.out, line);
+    }
+
+    /**
+     * Returns the color of the player to move.
+     *
+     * @return The color of the player to move.
+     */
+    fn side_to_move(&self) -> Color {
+        from_idx(self.game.board.board_info.turn)
+    }
+
+    /**
+     * Plays a move made by the opponent and starts thinking if the engine is to move next.
+     *
+     * The move may be given in coordinate notation or in Standard Algebraic Notation.
+     *
+     * @param input - The move sent by the GUI.
+     */
+    fn user_move(&mut self, input: &str) {
+        self.cancel_search();
+        let board = &self.game.board;
+        let Some(m) = Move::from_uci(board, input)
+            .ok()
+            .or_else(|| parse_move(board, input).ok())
+        else {
+            self.send(&format!("Illegal move: {}", input));
+            return;
+        };
+        match apply_legal_move(self.game.clone(), m) {
+            Ok(game) => self.game = game,
+            Err(()) => {
+                self.send(&format!("Illegal move: {}", input));
+                return;
+            }
+        }
+        if !self.announce_result() {
+            self.think();
+        }
+    }
+
+    /**
+     * Takes back the given number of plies.
+     *
+     * @param plies - The number of plies to take back.
+     */
+    fn take_back(&mut self, plies: usize) {
+        self.cancel_search();
+        for _ in 0..plies {
+            self.game = undo_move(self.game.clone());
+        }
+    }
+
+    /**
+     * Sends the result of the game if it has ended.
+     *
+     * @return true if the game has ended, false otherwise.
+     */
+    fn announce_result(&self) -> bool {
+        let status = self.game.game_state.status;
+        match status.result() {
+            Some(result) => {
+                self.send(&format!("{} {{{}}}", result, status));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /**
+     * Returns the limits for a search by the engine, from the time control, the clocks and the depth limit.
+     *
+     * @return The limits for the engine's next search.
+     */
+    pub fn search_limits(&self) -> SearchLimits {
+        let (wtime, btime) = match self.engine_color {
+            Color::White => (self.engine_time, self.opponent_time),
+            Color::Black => (self.opponent_time, self.engine_time),
+        };
+        let movestogo = self.moves_per_control.map(|moves| {
+            let played = self.game.board.board_info.fullmove_number - 1;
+            moves - played % moves
+        });
+        SearchLimits {
+            depth: self.depth,
+            movetime: self.move_time,
+            wtime,
+            btime,
+            winc: self.increment,
+            binc: self.increment,
+            movestogo,
+            ..SearchLimits::default()
+        }
+    }
+
+    /**
+     * Starts a search with the engine's `PlayerKind::Computer` brain if the engine is to move.
+     *
+     * The search runs in the background and reports its result as an `Event::Searched`.
+     */
+    fn think(&mut self) {
+        let color = self.side_to_move();
+        if self.force || self.searching || color != self.engine_color {
+            return;
+        }
+        if self.game.game_state.status.is_over() {
+            return;
+        }
+
+        let board = self.game.board.clone();
+        let player = self.game.game_state.player_mut(color);
+        if !matches!(player.kind, PlayerKind::Computer(_)) {
+            player.kind = PlayerKind::Computer(Box::new(Brain::new(board.clone(), color)));
+        }
+        let PlayerKind::Computer(brain) = &player.kind else {
+            return;
+        };
+        let mut brain = (**brain).clone();
+        brain.board = board.clone();
+        brain.tt = self.tt.clone();
+
+        self.stop.store(false, Ordering::Relaxed);
+        self.search_id += 1;
+        self.searching = true;
+        brain.stop = self.stop.clone();
+        let limits = self.search_limits();
+        let started = Instant::now();
+        if self.post {
+            let (out, pv_board) = (self.out.clone(), board.clone());
+            brain.reporter = Some(Arc::new(move |result: &SearchResult| {
+                send(&out, &thinking_line(&pv_board, result, started.elapsed()));
+            }));
+        }
+
+        let (id, events) = (self.search_id, self.events.clone());
+        self.search = Some(thread::spawn(move || {
+            let result = brain.best_move(&board, &limits);
+            let _ = events.send(Event::Searched(id, result));
+        }));
+    }
+
+    /**
+     * Plays the move found by the engine's search and announces it.
+     *
+     * @param result - The result of the engine's search.
+     */
+    fn play_engine_move(&mut self, result: SearchResult) {
+        let Some(m) = result.best_move else {
+            return;
+        };
+        let uci = m.to_uci();
+        if let Ok(game) = apply_legal_move(self.game.clone(), m) {
+            self.game = game;
+            self.send(&format!("move {}", uci));
+            self.announce_result();
+        }
+    }
+
+    /**
+     * Stops the running search, if any, waits for it to end and discards its result.
+     *
+     * Waiting keeps the stopped search from running on, holding the transposition table, after the next search
+     * has reset the stop flag.
+     */
+    fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            let _ = search.join();
+        }
+        self.searching = false;
+    }
+}
+
+/**
+ * Parses the arguments of a `level` command.
+ *
+ * The arguments are the number of moves per time control (0 for the whole game), the base time in minutes,
+ * optionally with seconds as in "2:30", and the increment in seconds.
+ *
+ * @param args - The arguments of the `level` command.
+ * @return The moves per control, base time and increment, or None if the arguments are malformed.
+ */
+pub fn parse_level(args: &str) -> Option<(Option<u32>, Duration, Duration)> {
+    let fields: Vec<&str> = args.split_whitespace().collect();
+    let [moves, base, increment] = fields.as_slice() else {
+        return None;
+    };
+    let moves: u32 = moves.parse().ok()?;
+    let base = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?
+        }
+        None => base.parse::<u64>().ok()? * 60,
+    };
+    let increment: f64 = increment.parse().ok()?;
+    if !increment.is_finite() || increment < 0.0 {
+        return None;
+    }
+    Some((
+        (moves > 0).then_some(moves),
+        Duration::from_secs(base),
+        Duration::from_secs_f64(increment),
+    ))
+}
+
+/**
+ * Formats a search result as an xboard thinking line: depth, score, time in centiseconds, nodes and the
+ * principal variation in Standard Algebraic Notation. Mate scores use the 100000 + moves convention.
+ *
+ * @param board - The position the search started from.
+ * @param result - The result of a completed search iteration.
+ * @param elapsed - The time spent searching so far.
+ * @return The thinking line.
+ */
+pub fn thinking_line(board: &Board, result: &SearchResult, elapsed: Duration) -> String {
+    let mut board = board.clone();
+    let mut pv = Vec::new();
+    for m in &result.pv {
+        pv.push(to_san(&board, m));
+        board.make_move(m.clone());
+        board.update();
+    }
+    // Mate scores are sent as 100000 plus the number of moves to mate
+    let score = match result.mate_in() {
+        Some(moves) if moves > 0 => 100000 + moves,
+        Some(moves) => -100000 + moves,
+        None => result.score,
+    };
+    format!(
+        "{} {} {} {} {}",
+        result.depth,
+        score,
+        elapsed.as_millis() / 10,
+        result.nodes,
+        pv.join(" ")
+    )
+}
+
+/**
+ * Runs the engine in xboard mode, reading commands from the standard input until `quit` or the end of the input.
+ */
+pub fn run() {
+    let (sender, events) = mpsc::channel();
+    let input = sender.clone();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if input.send(Event::Command(line)).is_err() {
+                return;
+            }
+        }
+        let _ = input.send(Event::Command(String::from("quit")));
+    });
+
+    let mut xboard = Xboard::new(Arc::new(Mutex::new(std::io::stdout())), sender);
+    for event in events {
+        if !xboard.handle(event) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::player::Color;
+    use crate::protocol::xboard::{parse_level, Event, Xboard};
+    use std::sync::mpsc::{self, Receiver};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    type Output = Arc<Mutex<Vec<u8>>>;
+
+    /**
+     * Creates an xboard engine writing into a buffer that the test can read.
+     *
+     * @return The engine, its output buffer and the receiver of its search results.
+     */
+    fn engine() -> (Xboard<Vec<u8>>, Output, Receiver<Event>) {
+        let out = Arc::new(Mutex::new(Vec::new()));
+        let (sender, events) = mpsc::channel();
+        (Xboard::new(out.clone(), sender), out, events)
+    }
+
+    /**
+     * Sends each command to the engine.
+     *
+     * @param xboard - The engine to send the commands to.
+     * @param commands - The command lines to send.
+     */
+    fn send_all(xboard: &mut Xboard<Vec<u8>>, commands: &[&str]) {
+        for command in commands {
+            assert!(xboard.handle(Event::Command(command.to_string())));
+        }
+    }
+
+    /**
+     * Takes the lines written to the buffer so far.
+     *
+     * @param out - The output buffer of the engine.
+     * @return The lines written since the last call.
+     */
+    fn take_lines(out: &Mutex<Vec<u8>>) -> Vec<String> {
+        let bytes = std::mem::take(&mut *out.lock().unwrap());
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    /**
+     * Waits for the engine's search to finish and passes the result back to the engine.
+     *
+     * @param xboard - The engine that is searching.
+     * @param events - The receiver of the engine's search results.
+     */
+    fn finish_search(xboard: &mut Xboard<Vec<u8>>, events: &Receiver<Event>) {
+        let event = events.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(xboard.handle(event));
+    }
+
+    #[test]
+    /**
+     * Tests feature negotiation, pings and unknown commands.
+     */
+    fn test_xboard_handshake() {
+        let (mut xboard, out, _events) = engine();
+        send_all(
+            &mut xboard,
+            &["xboard", "protover 2", "accepted usermove", "ping 7"],
+        );
+        let lines = take_lines(&out);
+        assert!(lines[0].starts_with("feature myname=\"Rust-Chess"));
+        assert!(lines[0].contains("setboard=1") && lines[0].contains("usermove=1"));
+        assert!(lines[0].contains("memory=1"));
+        assert!(lines[0].ends_with("done=1"));
+        assert_eq!(lines[1], "pong 7");
+
+        send_all(&mut xboard, &["memory 2", "memory none", "fly"]);
+        assert_eq!(
+            take_lines(&out),
+            ["Error (bad memory): none", "Error (unknown command): fly"]
+        );
+        assert!(!xboard.handle(Event::Command(String::from("quit"))));
+    }
+
+    #[test]
+    /**
+     * Tests that the engine answers moves as Black after `new` and plays nothing in force mode.
+     */
+    fn test_xboard_play() {
+        let (mut xboard, out, events) = engine();
+        send_all(&mut xboard, &["new", "sd 2", "post", "usermove e2e4"]);
+        finish_search(&mut xboard, &events);
+        let lines = take_lines(&out);
+        assert!(lines.last().unwrap().starts_with("move "));
+        assert_eq!(xboard.game.board.board_info.turn, 0);
+        assert_eq!(xboard.game.game_state.move_history.len(), 2);
+
+        send_all(
+            &mut xboard,
+            &[
+                "remove",
+                "force",
+                "usermove d2d4",
+                "usermove Nf6",
+                "usermove e2e5",
+            ],
+        );
+        assert_eq!(take_lines(&out), ["Illegal move: e2e5"]);
+        assert_eq!(
+            xboard.game.game_state.fen,
+            "rnbqkb1r/pppppppp/5n2/8/3P4/8/PPP1PPPP/RNBQKBNR w KQkq - 1 2"
+        );
+        assert!(events.try_recv().is_err());
+
+        send_all(&mut xboard, &["undo"]);
+        assert_eq!(xboard.game.game_state.move_history.len(), 1);
+    }
+
+    #[test]
+    /**
+     * Tests `setboard` and `go`, and that the engine announces the result when the game ends.
+     */
+    fn test_xboard_setboard_and_result() {
+        let (mut xboard, out, events) = engine();
+        send_all(
+            &mut xboard,
+            &[
+                "new",
+                "sd 2",
+                "force",
+                "setboard 4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1",
+                "go",
+            ],
+        );
+        assert_eq!(xboard.engine_color, Color::White);
+        finish_search(&mut xboard, &events);
+        assert_eq!(take_lines(&out), ["move e4d5"]);
+
+        send_all(&mut xboard, &["setboard 8/8/8"]);
+        assert!(take_lines(&out)[0].starts_with("tellusererror Illegal position"));
+
+        send_all(
+            &mut xboard,
+            &[
+                "force",
+                "setboard rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2",
+                "usermove d8h4",
+            ],
+        );
+        assert_eq!(take_lines(&out), ["0-1 {Checkmate, Black wins}"]);
+
+        send_all(&mut xboard, &["result 0-1 {Black mates}"]);
+        assert!(xboard.force);
+    }
+
+    #[test]
+    /**
+     * Tests that cancelling a search waits for it to end, so the next search starts right away.
+     */
+    fn test_xboard_cancel_search() {
+        let (mut xboard, out, events) = engine();
+        send_all(&mut xboard, &["new", "st 30", "force", "go"]);
+        thread::sleep(Duration::from_millis(50));
+        send_all(&mut xboard, &["force"]);
+
+        // The cancelled search has already sent its result, which is discarded
+        let cancelled = events.try_recv().unwrap();
+        assert!(xboard.handle(cancelled));
+        assert!(take_lines(&out).is_empty());
+
+        send_all(&mut xboard, &["sd 2", "go"]);
+        finish_search(&mut xboard, &events);
+        assert!(take_lines(&out)[0].starts_with("move "));
+    }
+
+    #[test]
+    /**
+     * Tests that time controls, clocks and depth limits become the engine's search limits.
+     */
+    fn test_xboard_time_control() {
+        assert_eq!(
+            parse_level("40 5 0"),
+            Some((Some(40), Duration::from_secs(300), Duration::ZERO))
+        );
+        assert_eq!(
+            parse_level("0 2:30 1.5"),
+            Some((None, Duration::from_secs(150), Duration::from_millis(1500)))
+        );
+        assert_eq!(parse_level("40 5"), None);
+        assert_eq!(parse_level("40 x 0"), None);
+
+        let (mut xboard, out, _events) = engine();
+        send_all(
+            &mut xboard,
+            &[
+                "new",
+                "level 40 5 2",
+                "time 30000",
+                "otim 25000",
+                "sd 6",
+                "force",
+            ],
+        );
+        let limits = xboard.search_limits();
+        assert_eq!(limits.btime, Some(Duration::from_secs(300)));
+        assert_eq!(limits.wtime, Some(Duration::from_secs(250)));
+        assert_eq!(limits.binc, Duration::from_secs(2));
+        assert_eq!(limits.movestogo, Some(40));
+        assert_eq!(limits.depth, Some(6));
+        assert_eq!(limits.movetime, None);
+
+        send_all(&mut xboard, &["st 3", "usermove e2e4", "usermove e7e5"]);
+        let limits = xboard.search_limits();
+        assert_eq!(limits.movetime, Some(Duration::from_secs(3)));
+        assert_eq!(limits.movestogo, Some(39));
+
+        send_all(&mut xboard, &["sd deep"]);
+        assert_eq!(take_lines(&out), ["Error (bad depth): deep"]);
+    }
+}
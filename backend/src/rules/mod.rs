@@ -1,13 +1,222 @@
 pub mod r#move;
+pub mod move_list;
+pub mod perft;
 
-use crate::board::board_info::BoardInfo;
-use crate::board::in_bounds;
+use crate::board::attacks::{king_attacks, knight_attacks, pawn_attacks, piece_attacks};
+use crate::board::board_info::{bb_squares, pos_to_bb, Bitboard, BoardInfo};
 use crate::board::piece::{Piece, PieceKind};
-use crate::game::player::Color;
-use crate::rules::r#move::{CastleType, Move, MoveType};
-use std::cmp::{max, min};
+use crate::board::{idx, Position, Square};
+use crate::game::player::{from_idx, Color};
+use crate::rules::move_list::{
+    MoveList, PackedMove, CAPTURE, DOUBLE_PAWN_PUSH, EN_PASSANT, KING_CASTLE, QUEEN_CASTLE, QUIET,
+};
+use crate::rules::r#move::{CastleType, Move};
 
+// Piece kinds a pawn promotes to, in the order their promotions are generated
+const PROMOTION_ORDER: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
 
+/**
+ * Generates the pseudo-legal moves of every piece of the given color into a move list.
+ *
+ * The moves follow the rules of movement but may leave the moving side's king in check. Pieces are visited by
+ * kind and then from a1 to h8.
+ *
+ * @param board_info - The board information with up to date squares and bitboards.
+ * @param color - The color of the pieces to generate the moves for.
+ * @param moves - The move list to add the moves to.
+ */
+pub fn generate_moves(board_info: &BoardInfo, color: Color, moves: &mut MoveList) {
+    for from in bb_squares(board_info.pawn(color)) {
+        pawn_moves(board_info, color, from, moves);
+    }
+    for kind in [
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+    ] {
+        for from in bb_squares(board_info.get_piece_bitboard(kind, color)) {
+            let targets = piece_attacks(kind, color, from, board_info.get_all_pieces_bitboard());
+            target_moves(board_info, color, from, targets, moves);
+        }
+    }
+    for from in bb_squares(board_info.king(color)) {
+        king_moves(board_info, color, from, moves);
+    }
+}
+
+/**
+ * Generates the legal moves of the given color into a move list.
+ *
+ * @param board_info - The board information with up to date squares and bitboards.
+ * @param color - The color of the player to move.
+ * @param moves - The move list to add the moves to.
+ */
+pub fn generate_legal_moves(board_info: &BoardInfo, color: Color, moves: &mut MoveList) {
+    let mut pseudo_moves = MoveList::new();
+    generate_moves(board_info, color, &mut pseudo_moves);
+    for &m in pseudo_moves.iter() {
+        if is_legal(board_info, color, m) {
+            moves.push(m);
+        }
+    }
+}
+
+/**
+ * Returns the squares the pawns of the given color can move to, ignoring whether the move would leave their king
+ * in check.
+ *
+ * These are the target squares of the pushes, captures and en passant captures generated for the pawns, found
+ * for all pawns at once without building the moves.
+ *
+ * @param board_info - The board information with up to date bitboards.
+ * @param color - The color of the pawns.
+ * @return The bitboard of squares the pawns can move to.
+ */
+pub fn pawn_move_targets(board_info: &BoardInfo, color: Color) -> Bitboard {
+    let pawns = board_info.pawn(color);
+    let empty = !board_info.get_all_pieces_bitboard();
+    let (single, double) = match color {
+        Color::White => {
+            let single = pawns << 8 & empty;
+            (single, (single & 0xff << 16) << 8 & empty)
+        }
+        Color::Black => {
+            let single = pawns >> 8 & empty;
+            (single, (single & 0xff << 40) >> 8 & empty)
+        }
+    };
+    let attacks = bb_squares(pawns).fold(0, |attacks, from| attacks | pawn_attacks(color, from));
+    let mut targets = single | double | attacks & board_info.get_player_bitboard(color.other());
+    if let Some(target) = board_info.en_passant_target {
+        if attacks & pos_to_bb(target) != 0 && en_passant_available(board_info, color, target) {
+            targets |= pos_to_bb(target);
+        }
+    }
+    targets
+}
+
+/**
+ * Returns the squares the king of the given color can castle to.
+ *
+ * @param board_info - The board information with up to date squares and enemy attack bitboard.
+ * @param color - The color of the king.
+ * @return The bitboard of the king's castling target squares, empty if it cannot castle.
+ */
+pub fn castle_targets(board_info: &BoardInfo, color: Color) -> Bitboard {
+    let mut targets = 0;
+    for from in bb_squares(board_info.king(color)) {
+        let Some(king) = board_info.squares[from] else {
+            continue;
+        };
+        for (castle_type, to_x) in [(CastleType::KingSide, 6), (CastleType::QueenSide, 2)] {
+            if can_castle(board_info, king, castle_type) {
+                targets |= 1 << (from / 8 * 8 + to_x);
+            }
+        }
+    }
+    targets
+}
+
+/**
+ * Expands the packed moves of a single piece into full moves.
+ *
+ * @param moves - The packed moves of the piece.
+ * @param piece - The piece making the moves.
+ * @return The full moves, in the order they were generated.
+ */
+fn piece_move_vec(moves: &MoveList, piece: Piece) -> Vec<Move> {
+    moves.iter().map(|m| m.with_piece(piece)).collect()
+}
+
+// Function to generate all moves for a pawn at a given position
+/**
+ * Generates a list of possible moves for a pawn on the given chessboard.
+ *
+ * This function calculates the single and double pushes, diagonal captures, en passant captures and
+ * promotions available to the pawn. White pawns move towards rank 7 and black pawns towards rank 0.
+ * Promotions are expanded into one move per promotion piece.
+ *
+ * @param board_info - The board information containing the current state of the chessboard.
+ * @param piece - The pawn for which the moves are to be generated.
+ *
+ * @return A vector of Move objects representing the possible moves for the pawn.
+ */
+pub fn generate_pawn_moves(board_info: &BoardInfo, piece: Piece) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    pawn_moves(board_info, piece.color, idx(piece.position), &mut moves);
+    piece_move_vec(&moves, piece)
+}
+
+/**
+ * Generates the pushes, captures, en passant captures and promotions of a pawn into a move list.
+ *
+ * @param board_info - The board information containing the current state of the chessboard.
+ * @param color - The color of the pawn.
+ * @param from - The index of the pawn's square.
+ * @param moves - The move list to add the moves to.
+ */
+fn pawn_moves(board_info: &BoardInfo, color: Color, from: usize, moves: &mut MoveList) {
+    let (forward, start_rank, promotion_rank): (isize, usize, usize) = match color {
+        Color::White => (8, 1, 7),
+        Color::Black => (-8, 6, 0),
+    };
+    let empty = !board_info.get_all_pieces_bitboard();
+    let push_targets = |to: usize, flags: u16, moves: &mut MoveList| {
+        if to / 8 == promotion_rank {
+            for kind in PROMOTION_ORDER {
+                moves.push(PackedMove::promotion(from, to, kind, flags == CAPTURE));
+            }
+        } else {
+            moves.push(PackedMove::new(from, to, flags));
+        }
+    };
+
+    // Single and double pushes
+    let one_step = from as isize + forward;
+    if (0..64).contains(&one_step) && empty & (1 << one_step) != 0 {
+        push_targets(one_step as usize, QUIET, moves);
+        let two_step = (one_step + forward) as usize;
+        if from / 8 == start_rank && empty & (1 << two_step) != 0 {
+            moves.push(PackedMove::new(from, two_step, DOUBLE_PAWN_PUSH));
+        }
+    }
+
+    // Diagonal captures
+    let attacks = pawn_attacks(color, from);
+    for to in bb_squares(attacks & board_info.get_player_bitboard(color.other())) {
+        push_targets(to, CAPTURE, moves);
+    }
+    if let Some(target) = board_info.en_passant_target {
+        if attacks & pos_to_bb(target) != 0 && en_passant_available(board_info, color, target) {
+            moves.push(PackedMove::new(from, idx(target), EN_PASSANT));
+        }
+    }
+}
+
+/**
+ * Checks whether a pawn of the given color may capture en passant onto the target square.
+ *
+ * En passant is only available onto the en passant target square left by the enemy's last double push, which lies
+ * on rank 5 for white pawns and rank 2 for black pawns.
+ *
+ * @param board_info - The board information containing the en passant target.
+ * @param color - The color of the capturing pawn.
+ * @param target - The empty square the capturing pawn would move to.
+ * @return true if the pawn can capture en passant onto the target square, false otherwise.
+ */
+fn en_passant_available(board_info: &BoardInfo, color: Color, target: (u8, u8)) -> bool {
+    let capture_rank = match color {
+        Color::White => 5,
+        Color::Black => 2,
+    };
+    target.1 == capture_rank && board_info.en_passant_target == Some(target)
+}
 
 // Function to generate all legal moves for a knight at a given position
 /**
@@ -20,203 +229,631 @@ use std::cmp::{max, min};
  *
  * @return A vector of Move objects representing the possible moves for the knight.
  */
-pub fn generate_knight_moves(board_info: BoardInfo, piece: Piece) -> Vec<Move> {
-    let mut moves = Vec::new();
-    let color = piece.color;
-    let from_pos = piece.position;
-    let (x, y) = from_pos;
+pub fn generate_knight_moves(board_info: &BoardInfo, piece: Piece) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    let from = idx(piece.position);
+    target_moves(
+        board_info,
+        piece.color,
+        from,
+        knight_attacks(from),
+        &mut moves,
+    );
+    piece_move_vec(&moves, piece)
+}
 
-    // The knight can move in 8 directions: up up left/right, down down left/right, left left up/down, right right up/down
-    let directions = [
-        (-2, -1),
-        (-2, 1),
-        (-1, -2),
-        (-1, 2),
-        (1, -2),
-        (1, 2),
-        (2, -1),
-        (2, 1),
-    ];
+// Function to generate all legal moves for a sliding piece at a given position
+/**
+ * Generates a list of possible moves for a rook, bishop or queen on the given chessboard.
+ *
+ * The attacked squares are looked up in the magic bitboard tables, so every ray stops at the first piece on it.
+ *
+ * @param board_info - The board information containing the current state of the chessboard.
+ * @param piece - The sliding piece for which the moves are to be generated.
+ *
+ * @return A vector of Move objects representing the possible moves for the sliding piece.
+ */
+pub fn generate_sliding_move(board_info: &BoardInfo, piece: Piece) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    let from = idx(piece.position);
+    let occupied = board_info.get_all_pieces_bitboard();
+    let targets = piece_attacks(piece.kind, piece.color, from, occupied);
+    target_moves(board_info, piece.color, from, targets, &mut moves);
+    piece_move_vec(&moves, piece)
+}
 
-    for direction in &directions {
-        let to_pos = ((x as i8 + direction.0) as u8, (y as i8 + direction.1) as u8);
-        capture_or_normal(board_info.clone(), color, piece, to_pos, &mut moves);
+// Function to generate all moves for a king at a given position
+/**
+ * Generates a list of possible moves for a king on the given chessboard.
+ *
+ * This function calculates the one-step moves in all eight directions and the castling moves
+ * available to the king.
+ *
+ * @param board_info - The board information containing the current state of the chessboard.
+ * @param piece - The king for which the moves are to be generated.
+ *
+ * @return A vector of Move objects representing the possible moves for the king.
+ */
+pub fn generate_king_moves(board_info: &BoardInfo, piece: Piece) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    king_moves(board_info, piece.color, idx(piece.position), &mut moves);
+    piece_move_vec(&moves, piece)
+}
+
+/**
+ * Generates the one-step moves and castling moves of a king into a move list.
+ *
+ * @param board_info - The board information containing the current state of the chessboard.
+ * @param color - The color of the king.
+ * @param from - The index of the king's square.
+ * @param moves - The move list to add the moves to.
+ */
+fn king_moves(board_info: &BoardInfo, color: Color, from: usize, moves: &mut MoveList) {
+    target_moves(board_info, color, from, king_attacks(from), moves);
+    let Some(king) = board_info.squares[from] else {
+        return;
+    };
+    for (castle_type, to_x, flags) in [
+        (CastleType::KingSide, 6, KING_CASTLE),
+        (CastleType::QueenSide, 2, QUEEN_CASTLE),
+    ] {
+        if can_castle(board_info, king, castle_type) {
+            moves.push(PackedMove::new(from, from / 8 * 8 + to_x, flags));
+        }
     }
-    moves
 }
 
-// Function to generate all legal moves for a sliding piece at a given position
-pub fn generate_sliding_move(board_info: BoardInfo, piece: Piece) -> Vec<Move> {
-    let mut moves = Vec::new();
-    let from_pos = piece.position;
-    let color = piece.color;
-    let directions: Vec<(i8, i8)>;
-    // horizontal and vertical directions
-    let hdirections = [(-1, 0), (0, -1), (0, 1), (1, 0)];
-    let ddirections = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
-    // diagonal directions
-    if piece.kind == PieceKind::Queen {
-        directions = hdirections
+/**
+ * Checks whether the king can castle to the given side.
+ *
+ * Castling requires the castling right for the chosen side, the king and the rook on that side to be on
+ * their original squares without having moved, and every square between them to be empty. The king may not
+ * castle out of check, and the squares it passes over and lands on may not be attacked.
+ *
+ * @param board_info - The board information containing the current state of the chessboard.
+ * @param king - The king that wants to castle.
+ * @param castle_type - The side to castle to.
+ * @return true if the king can castle to the given side, false otherwise.
+ */
+fn can_castle(board_info: &BoardInfo, king: Piece, castle_type: CastleType) -> bool {
+    let back_rank = match king.color {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    let has_right = match (king.color, castle_type) {
+        (Color::White, CastleType::KingSide) => board_info.white_can_castle_kingside,
+        (Color::White, CastleType::QueenSide) => board_info.white_can_castle_queenside,
+        (Color::Black, CastleType::KingSide) => board_info.black_can_castle_kingside,
+        (Color::Black, CastleType::QueenSide) => board_info.black_can_castle_queenside,
+    };
+    if !has_right || king.has_moved || king.position != (4, back_rank) {
+        return false;
+    }
+
+    let (rook_x, between, king_path): (u8, &[u8], &[u8]) = match castle_type {
+        CastleType::KingSide => (7, &[5, 6], &[4, 5, 6]),
+        CastleType::QueenSide => (0, &[1, 2, 3], &[4, 3, 2]),
+    };
+    let rook_ready = matches!(
+        board_info.get_square((rook_x, back_rank)),
+        Some(rook) if rook.kind == PieceKind::Rook && rook.color == king.color && !rook.has_moved
+    );
+    if !rook_ready
+        || between
             .iter()
-            .chain(ddirections.iter())
-            .cloned()
-            .collect();
-    } else if piece.kind == PieceKind::Rook {
-        directions = hdirections.to_vec();
-    } else {
-        // Bishop
-        directions = ddirections.to_vec();
+            .any(|&x| board_info.get_square((x, back_rank)).is_some())
+    {
+        return false;
     }
 
-    for direction in directions.iter() {
-        let mut distance = 1;
-        loop {
-            let new_x = (from_pos.0 as i8 + distance * direction.0) as u8;
-            let new_y = (from_pos.1 as i8 + distance * direction.1) as u8;
-            let to_pos = (new_x, new_y);
+    king_path
+        .iter()
+        .all(|&x| !board_info.is_attacked((x, back_rank), king.color))
+}
 
-            if capture_or_normal(board_info.clone(), color, piece, to_pos, &mut moves) {
-                break;
-            }
-            distance += 1;
-        }
+/**
+ * Adds a move to every target square not occupied by a piece of the moving color.
+ *
+ * Moves onto squares occupied by an enemy piece are captures, all others are quiet moves.
+ *
+ * @param board_info - The board information containing the current state of the chessboard.
+ * @param color - The color of the moving piece.
+ * @param from - The index of the moving piece's square.
+ * @param targets - The bitboard of squares the piece attacks.
+ * @param moves - The move list to add the moves to.
+ */
+fn target_moves(
+    board_info: &BoardInfo,
+    color: Color,
+    from: usize,
+    targets: Bitboard,
+    moves: &mut MoveList,
+) {
+    let enemies = board_info.get_player_bitboard(color.other());
+    let targets = targets & !board_info.get_player_bitboard(color);
+    for to in bb_squares(targets & enemies) {
+        moves.push(PackedMove::new(from, to, CAPTURE));
     }
-    moves
+    for to in bb_squares(targets & !enemies) {
+        moves.push(PackedMove::new(from, to, QUIET));
+    }
+}
+
+/**
+ * Checks if a pseudo-legal move is legal.
+ *
+ * @param board_info - The board information before the move, with up to date bitboards.
+ * @param m - The pseudo-legal move to check.
+ * @return true if the move does not leave the moving side's king in check, false otherwise.
+ */
+pub fn is_legal_move(board_info: &BoardInfo, m: &Move) -> bool {
+    is_legal(board_info, m.color, PackedMove::from(m))
 }
 
-// Pushes all promotion piece types moves to the list of moves
 /**
- * Generates a list of promotion moves.
+ * Checks if a pseudo-legal packed move is legal.
  *
- * This function takes in the board information, the color of the player, the from and to positions of the move,
- * and generates a list of possible promotion moves for the given piece.
+ * This function updates the occupancy for the move and looks up the enemy pieces that would attack the moving
+ * side's king afterwards, which rules out moving pinned pieces off their pin line, ignoring a check, stepping
+ * into a check and en passant captures that uncover an attack along the rank. A piece captured by the move no
+ * longer attacks. Castling out of or through check is already ruled out when the castling move is generated.
+ * Boards without a king of the moving color have no illegal moves.
  *
- * @param board_info - The board information containing the current state of the game board.
- * @param _color - The color of the player.
- * @param fmv - The from position of the move.
- * @param pmv - The to position of the move.
- * @return A vector of Move objects representing the possible promotion moves.
+ * @param board_info - The board information before the move, with up to date bitboards, checkers and pinned pieces.
+ * @param color - The color of the moving piece.
+ * @param m - The pseudo-legal move to check.
+ * @return true if the move does not leave the moving side's king in check, false otherwise.
  */
-pub fn promotion_move(
-    board_info: BoardInfo,
-    _color: Color,
-    fmv: (u8, u8),
-    pmv: (u8, u8),
-) -> Vec<Move> {
-    let mut moves = vec![];
-    let piece = board_info.get_square(fmv).unwrap();
-    let to_pos = pmv;
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::Promotion(PieceKind::Queen),
-        piece.color,
-    ));
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::Promotion(PieceKind::Rook),
-        piece.color,
-    ));
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::Promotion(PieceKind::Bishop),
-        piece.color,
-    ));
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::Promotion(PieceKind::Knight),
-        piece.color,
-    ));
-    moves
-}
-
-// Pushes all promotion piece types attack moves to the list of moves
-/**
- * Generates promotion attack moves for a piece on the specified board.
- *
- * This function takes in the board information, piece color, from position, and to position.
- * It generates a vector of moves representing all possible promotion attack moves for the piece.
- *
- * @param board_info - The board information containing the piece positions.
- * @param color - The color of the piece.
- * @param fmv - The from position of the piece.
- * @param pmv - The to position for the piece.
- * @return A vector of Move objects representing the promotion attack moves.
- */
-pub fn promotion_attack_move(
-    board_info: BoardInfo,
-    _color: Color,
-    fmv: (u8, u8),
-    pmv: (u8, u8),
-) -> Vec<Move> {
-    let mut moves = vec![];
-    let piece = board_info.get_square(fmv).unwrap();
-    let to_pos = pmv;
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::PromotionCapture(PieceKind::Queen),
-        piece.color,
-    ));
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::PromotionCapture(PieceKind::Rook),
-        piece.color,
-    ));
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::PromotionCapture(PieceKind::Bishop),
-        piece.color,
-    ));
-    moves.push(Move::new(
-        piece,
-        to_pos,
-        MoveType::PromotionCapture(PieceKind::Knight),
-        piece.color,
-    ));
-    moves
+pub fn is_legal(board_info: &BoardInfo, color: Color, m: PackedMove) -> bool {
+    let king = board_info.king(color);
+    if king == 0 {
+        return true;
+    }
+    let from = 1 << m.from_square();
+
+    // Without a check, only the king, pinned pieces and en passant captures can expose the king
+    if color == from_idx(board_info.turn)
+        && board_info.checkers == 0
+        && from & (king | board_info.pinned) == 0
+        && !m.is_en_passant()
+    {
+        return true;
+    }
+
+    let to = 1 << m.to_square();
+    let mut occupied = (board_info.get_all_pieces_bitboard() & !from) | to;
+    let mut enemies = board_info.get_player_bitboard(color.other()) & !to;
+
+    if m.is_castle() {
+        let (rook_from, rook_to) = if m.to_square() > m.from_square() {
+            (to << 1, to >> 1)
+        } else {
+            (to >> 2, to << 1)
+        };
+        occupied = (occupied & !rook_from) | rook_to;
+    } else if m.is_en_passant() {
+        let captured = pos_to_bb((m.to_pos().0, m.from_pos().1));
+        occupied &= !captured;
+        enemies &= !captured;
+    }
+
+    let king_pos: Position = if king & from != 0 {
+        m.to_pos()
+    } else {
+        board_info.king_pos(color)
+    };
+    board_info.attackers_to(king_pos, occupied) & enemies == 0
 }
 
-pub fn capture_or_normal(
-    board_info: BoardInfo,
-    color: Color,
-    piece: Piece,
-    to_pos: (u8, u8),
-    moves: &mut Vec<Move>,
-) -> bool {
-    if in_bounds(to_pos) {
-        let nmv = Move::new(piece, to_pos, MoveType::Normal, piece.color);
-        let cmv = Move::new(piece, to_pos, MoveType::Capture, piece.color);
-
-        let to_square = board_info.get_square(to_pos);
-        match to_square {
-            Some(to_piece) if to_piece.color != color => {
-                moves.push(cmv);
-                return true;
-            }
-            None => {
-                moves.push(nmv);
-                return false;
-            }
-            _ => (),
+/**
+ * Checks if neither side has enough material left to deliver checkmate.
+ *
+ * The position is dead if only the kings remain, if one side has a single bishop or knight against a bare
+ * king, or if all remaining pieces besides the kings are bishops standing on squares of the same color.
+ *
+ * @param squares - The squares of the chessboard.
+ * @return true if checkmate is impossible for both sides, false otherwise.
+ */
+pub fn is_insufficient_material(squares: &[Square; 64]) -> bool {
+    let pieces: Vec<Piece> = squares
+        .iter()
+        .flatten()
+        .filter(|p| p.kind != PieceKind::King)
+        .copied()
+        .collect();
+
+    match pieces.as_slice() {
+        [] => true,
+        [piece] => matches!(piece.kind, PieceKind::Bishop | PieceKind::Knight),
+        _ => {
+            let square_color = |p: &Piece| (p.position.0 + p.position.1) % 2;
+            pieces.iter().all(|p| p.kind == PieceKind::Bishop)
+                && pieces
+                    .iter()
+                    .all(|p| square_color(p) == square_color(&pieces[0]))
         }
     }
-    true
 }
 
-
 #[cfg(test)]
 mod tests {
-    use crate::board::Board;
     use crate::board::board_info::BoardInfo;
     use crate::board::piece::{Piece, PieceKind};
+    use crate::board::{idx, Board};
     use crate::game::player::Color;
-    use crate::rules::generate_pawn_moves;
-    use crate::rules::r#move::{Move, MoveType};
+    use crate::rules::r#move::{CastleType, Move, MoveType};
+    use crate::rules::{generate_king_moves, generate_pawn_moves};
+
+    /**
+     * Creates an updated board with the given pieces placed on it.
+     *
+     * Both players keep all of their castling rights.
+     *
+     * @param pieces - The kind, position and color of every piece to place.
+     * @return The board with its board information updated for the placed pieces.
+     */
+    fn board_with(pieces: &[(PieceKind, (u8, u8), Color)]) -> Board {
+        let mut board = Board::new();
+        for &(kind, pos, color) in pieces {
+            board.squares[idx(pos)] = Some(Piece::new(kind, pos, color));
+        }
+        board.board_info.white_can_castle_kingside = true;
+        board.board_info.white_can_castle_queenside = true;
+        board.board_info.black_can_castle_kingside = true;
+        board.board_info.black_can_castle_queenside = true;
+        board.reset_zobrist_key();
+        board.update();
+        board
+    }
+
+    /**
+     * Counts the moves of the given type.
+     *
+     * @param moves - The moves to count.
+     * @param move_type - The move type to look for.
+     * @return The number of moves with the given move type.
+     */
+    fn count_type(moves: &[Move], move_type: MoveType) -> usize {
+        moves.iter().filter(|m| m.move_type == move_type).count()
+    }
+
+    /**
+     * Generates the pawn moves for the piece at the given position.
+     *
+     * @param board_info - The board information to generate the moves from.
+     * @param pos - The position of the pawn.
+     * @return The moves available to the pawn.
+     */
+    fn pawn_moves_at(board_info: &BoardInfo, pos: (u8, u8)) -> Vec<Move> {
+        let pawn = board_info.get_square(pos).unwrap();
+        generate_pawn_moves(board_info, pawn)
+    }
+
+    #[test]
+    /**
+     * Tests that pawns of both colors can push one or two squares from their starting rank.
+     */
+    fn test_pawn_pushes() {
+        let board = board_with(&[
+            (PieceKind::Pawn, (4, 1), Color::White),
+            (PieceKind::Pawn, (3, 6), Color::Black),
+        ]);
+
+        let white_moves = pawn_moves_at(&board.board_info, (4, 1));
+        assert_eq!(white_moves.len(), 2);
+        assert_eq!(count_type(&white_moves, MoveType::DoublePawnPush), 1);
+        assert!(white_moves.iter().any(|m| m.to == (4, 3)));
+
+        let black_moves = pawn_moves_at(&board.board_info, (3, 6));
+        assert_eq!(black_moves.len(), 2);
+        assert!(black_moves.iter().any(|m| m.to == (3, 4)));
+    }
+
+    #[test]
+    /**
+     * Tests that pieces in front of a pawn block its single and double pushes.
+     */
+    fn test_pawn_blocked() {
+        let board = board_with(&[
+            (PieceKind::Pawn, (4, 1), Color::White),
+            (PieceKind::Knight, (4, 3), Color::Black),
+            (PieceKind::Pawn, (2, 1), Color::White),
+            (PieceKind::Bishop, (2, 2), Color::White),
+        ]);
+
+        // The double push is blocked, the single push is not
+        assert_eq!(pawn_moves_at(&board.board_info, (4, 1)).len(), 1);
+        // A blocked pawn cannot jump over the blocker
+        assert!(pawn_moves_at(&board.board_info, (2, 1)).is_empty());
+    }
 
-}
\ No newline at end of file
+    #[test]
+    /**
+     * Tests that a pawn captures diagonally but not straight ahead.
+     */
+    fn test_pawn_captures() {
+        let board = board_with(&[
+            (PieceKind::Pawn, (3, 3), Color::White),
+            (PieceKind::Pawn, (2, 4), Color::Black),
+            (PieceKind::Rook, (4, 4), Color::Black),
+            (PieceKind::Pawn, (3, 4), Color::White),
+        ]);
+
+        let moves = pawn_moves_at(&board.board_info, (3, 3));
+        assert_eq!(moves.len(), 2);
+        assert_eq!(count_type(&moves, MoveType::Capture), 2);
+    }
+
+    #[test]
+    /**
+     * Tests that pushes and captures onto the last rank expand into all four promotions.
+     */
+    fn test_pawn_promotions() {
+        let board = board_with(&[
+            (PieceKind::Pawn, (0, 6), Color::White),
+            (PieceKind::Knight, (1, 7), Color::Black),
+            (PieceKind::Pawn, (7, 1), Color::Black),
+        ]);
+
+        let moves = pawn_moves_at(&board.board_info, (0, 6));
+        assert_eq!(moves.len(), 8);
+        for kind in [
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+        ] {
+            assert_eq!(count_type(&moves, MoveType::Promotion(kind)), 1);
+            assert_eq!(count_type(&moves, MoveType::PromotionCapture(kind)), 1);
+        }
+
+        let moves = pawn_moves_at(&board.board_info, (7, 1));
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|m| m.to == (7, 0)));
+    }
+
+    #[test]
+    /**
+     * Tests that en passant is generated right after an enemy double push and only then.
+     */
+    fn test_pawn_en_passant() {
+        let mut board = board_with(&[
+            (PieceKind::Pawn, (4, 4), Color::White),
+            (PieceKind::Pawn, (3, 6), Color::Black),
+        ]);
+        board.board_info.turn = 1;
+        board.reset_zobrist_key();
+
+        let double_push = pawn_moves_at(&board.board_info, (3, 6))
+            .into_iter()
+            .find(|m| m.move_type == MoveType::DoublePawnPush)
+            .unwrap();
+        board.make_move(double_push);
+        board.update();
+
+        let moves = pawn_moves_at(&board.board_info, (4, 4));
+        assert_eq!(count_type(&moves, MoveType::EnPassant), 1);
+        assert!(moves
+            .iter()
+            .any(|m| m.move_type == MoveType::EnPassant && m.to == (3, 5)));
+
+        // En passant is only available immediately after the double push
+        let push = moves
+            .iter()
+            .find(|m| m.move_type == MoveType::Normal)
+            .unwrap();
+        board.make_move(push.clone());
+        board.update();
+        assert!(board
+            .board_info
+            .black_psuedo_moves
+            .iter()
+            .all(|m| m.move_type != MoveType::EnPassant));
+    }
+
+    #[test]
+    /**
+     * Tests that the king moves one square in every direction and cannot land on friendly pieces.
+     */
+    fn test_king_moves() {
+        let board = board_with(&[
+            (PieceKind::King, (3, 3), Color::White),
+            (PieceKind::Pawn, (3, 4), Color::White),
+            (PieceKind::Pawn, (4, 4), Color::Black),
+        ]);
+        let king = board.get_piece((3, 3)).unwrap();
+
+        let moves = generate_king_moves(&board.board_info, king);
+        assert_eq!(moves.len(), 7);
+        assert_eq!(count_type(&moves, MoveType::Capture), 1);
+    }
+
+    #[test]
+    /**
+     * Tests that castling is generated for both sides when the king and rooks are unmoved.
+     */
+    fn test_king_castling() {
+        let board = board_with(&[
+            (PieceKind::King, (4, 0), Color::White),
+            (PieceKind::Rook, (0, 0), Color::White),
+            (PieceKind::Rook, (7, 0), Color::White),
+            (PieceKind::King, (4, 7), Color::Black),
+            (PieceKind::Rook, (0, 7), Color::Black),
+            (PieceKind::Knight, (6, 7), Color::Black),
+        ]);
+
+        let white_king = board.get_piece((4, 0)).unwrap();
+        let moves = generate_king_moves(&board.board_info, white_king);
+        assert_eq!(moves.len(), 7);
+        assert!(moves
+            .iter()
+            .any(|m| m.move_type == MoveType::Castle(CastleType::KingSide) && m.to == (6, 0)));
+        assert!(moves
+            .iter()
+            .any(|m| m.move_type == MoveType::Castle(CastleType::QueenSide) && m.to == (2, 0)));
+
+        // Black has no kingside rook and the knight blocks nothing on the queenside
+        let black_king = board.get_piece((4, 7)).unwrap();
+        let moves = generate_king_moves(&board.board_info, black_king);
+        assert_eq!(
+            count_type(&moves, MoveType::Castle(CastleType::KingSide)),
+            0
+        );
+        assert_eq!(
+            count_type(&moves, MoveType::Castle(CastleType::QueenSide)),
+            1
+        );
+    }
+
+    #[test]
+    /**
+     * Tests that castling is not generated through pieces or with a moved rook.
+     */
+    fn test_king_castling_blocked() {
+        let mut board = board_with(&[
+            (PieceKind::King, (4, 0), Color::White),
+            (PieceKind::Rook, (0, 0), Color::White),
+            (PieceKind::Knight, (1, 0), Color::White),
+            (PieceKind::Rook, (7, 0), Color::White),
+        ]);
+        let mut rook = board.get_piece((7, 0)).unwrap();
+        rook.has_moved = true;
+        board.squares[idx((7, 0))] = Some(rook);
+        board.update();
+
+        let king = board.get_piece((4, 0)).unwrap();
+        let moves = generate_king_moves(&board.board_info, king);
+        assert!(moves
+            .iter()
+            .all(|m| !matches!(m.move_type, MoveType::Castle(_))));
+    }
+
+    #[test]
+    /**
+     * Tests that a pinned piece may only move along the pin line.
+     */
+    fn test_legal_moves_pinned_piece() {
+        let board = board_with(&[
+            (PieceKind::King, (4, 0), Color::White),
+            (PieceKind::Rook, (4, 2), Color::White),
+            (PieceKind::Rook, (4, 7), Color::Black),
+            (PieceKind::King, (0, 7), Color::Black),
+        ]);
+
+        let rook_moves: Vec<Move> = board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|m| m.from == (4, 2))
+            .collect();
+        assert_eq!(rook_moves.len(), 6);
+        assert!(rook_moves.iter().all(|m| m.to.0 == 4));
+    }
+
+    #[test]
+    /**
+     * Tests that only king moves are legal in a double check.
+     */
+    fn test_legal_moves_double_check() {
+        let board = board_with(&[
+            (PieceKind::King, (4, 0), Color::White),
+            (PieceKind::Queen, (0, 3), Color::White),
+            (PieceKind::Rook, (4, 7), Color::Black),
+            (PieceKind::Bishop, (1, 3), Color::Black),
+            (PieceKind::King, (0, 7), Color::Black),
+        ]);
+
+        let moves = board.get_legal_moves();
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|m| m.from_piece.kind == PieceKind::King));
+    }
+
+    #[test]
+    /**
+     * Tests that a check must be answered by capturing, blocking or moving the king.
+     */
+    fn test_legal_moves_single_check() {
+        let board = board_with(&[
+            (PieceKind::King, (4, 0), Color::White),
+            (PieceKind::Rook, (0, 2), Color::White),
+            (PieceKind::Knight, (6, 5), Color::White),
+            (PieceKind::Rook, (4, 7), Color::Black),
+            (PieceKind::King, (0, 7), Color::Black),
+        ]);
+
+        let moves = board.get_legal_moves();
+        assert!(moves.iter().any(|m| m.from == (0, 2) && m.to == (4, 2)));
+        assert!(moves.iter().any(|m| m.from == (6, 5) && m.to == (4, 6)));
+        assert!(moves.iter().any(|m| m.from == (6, 5) && m.to == (4, 4)));
+        assert!(moves
+            .iter()
+            .filter(|m| m.from_piece.kind != PieceKind::King)
+            .all(|m| m.to.0 == 4));
+    }
+
+    #[test]
+    /**
+     * Tests that an en passant capture which uncovers a rook attack on the king is illegal.
+     */
+    fn test_legal_moves_en_passant_discovered_check() {
+        let mut board = board_with(&[
+            (PieceKind::King, (0, 4), Color::White),
+            (PieceKind::Pawn, (4, 4), Color::White),
+            (PieceKind::Pawn, (3, 6), Color::Black),
+            (PieceKind::Rook, (7, 4), Color::Black),
+            (PieceKind::King, (7, 7), Color::Black),
+        ]);
+        board.board_info.turn = 1;
+        board.reset_zobrist_key();
+        board.update();
+
+        let double_push = board
+            .get_legal_moves()
+            .into_iter()
+            .find(|m| m.move_type == MoveType::DoublePawnPush)
+            .unwrap();
+        board.make_move(double_push);
+        board.update();
+
+        assert!(board
+            .get_current_moves()
+            .iter()
+            .any(|m| m.move_type == MoveType::EnPassant));
+        assert!(board
+            .get_legal_moves()
+            .iter()
+            .all(|m| m.move_type != MoveType::EnPassant));
+    }
+
+    #[test]
+    /**
+     * Tests that the king may not castle out of, or through, an attacked square.
+     */
+    fn test_legal_moves_castling_through_check() {
+        let board = board_with(&[
+            (PieceKind::King, (4, 0), Color::White),
+            (PieceKind::Rook, (0, 0), Color::White),
+            (PieceKind::Rook, (7, 0), Color::White),
+            (PieceKind::Rook, (5, 7), Color::Black),
+            (PieceKind::King, (0, 7), Color::Black),
+        ]);
+        let castles: Vec<MoveType> = board
+            .get_legal_moves()
+            .into_iter()
+            .map(|m| m.move_type)
+            .filter(|t| matches!(t, MoveType::Castle(_)))
+            .collect();
+        assert_eq!(castles, vec![MoveType::Castle(CastleType::QueenSide)]);
+
+        let board = board_with(&[
+            (PieceKind::King, (4, 0), Color::White),
+            (PieceKind::Rook, (0, 0), Color::White),
+            (PieceKind::Rook, (7, 0), Color::White),
+            (PieceKind::Rook, (4, 7), Color::Black),
+            (PieceKind::King, (0, 7), Color::Black),
+        ]);
+        assert!(board
+            .get_legal_moves()
+            .iter()
+            .all(|m| !matches!(m.move_type, MoveType::Castle(_))));
+    }
+}
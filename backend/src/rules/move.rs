@@ -1,6 +1,7 @@
 use crate::board::piece::{Piece, PieceKind};
-use crate::board::Position;
+use crate::board::{algebraic_from_pos, pos_from_algebraic, Board, Position};
 use crate::game::player::Color;
+use std::error::Error;
 use std::fmt::Display;
 
 // Enum to represent different types of castle moves
@@ -22,6 +23,38 @@ pub enum MoveType {
     PromotionCapture(PieceKind),
 }
 
+// Enum to represent the reasons a move in UCI coordinate notation can be rejected
+#[derive(Clone, PartialEq, Debug)]
+pub enum UciMoveError {
+    Malformed(String),
+    Illegal(String),
+}
+
+impl Display for UciMoveError {
+    /**
+     * Formats the UCI move error as a human readable message.
+     *
+     * @param f - The formatter to write the message to.
+     * @return Result - Ok if the formatting is successful, Err otherwise.
+     */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciMoveError::Malformed(uci) => {
+                write!(
+                    f,
+                    "'{}' is not a move in coordinate notation such as e2e4",
+                    uci
+                )
+            }
+            UciMoveError::Illegal(uci) => {
+                write!(f, "'{}' is not a legal move in this position", uci)
+            }
+        }
+    }
+}
+
+impl Error for UciMoveError {}
+
 // Struct to represent a move
 #[derive(Clone, Debug, PartialEq)]
 pub struct Move {
@@ -148,20 +181,89 @@ impl Move {
             MoveType::Capture | MoveType::PromotionCapture(_) | MoveType::EnPassant
         )
     }
+
+    /**
+     * Returns the piece kind the move promotes to.
+     *
+     * @return The promotion piece kind, or None if the move is not a promotion.
+     */
+    pub fn promotion(&self) -> Option<PieceKind> {
+        match self.move_type {
+            MoveType::Promotion(kind) | MoveType::PromotionCapture(kind) => Some(kind),
+            _ => None,
+        }
+    }
+
+    /**
+     * Converts the move to UCI coordinate notation.
+     *
+     * The notation is the square the piece moves from followed by the square it moves to, plus the lowercase
+     * letter of the promotion piece, such as "e2e4" or "e7e8q". Castling is written as the king's move, "e1g1".
+     *
+     * @return The move in UCI coordinate notation.
+     */
+    pub fn to_uci(&self) -> String {
+        let mut uci = algebraic_from_pos(self.from) + &algebraic_from_pos(self.to);
+        if let Some(kind) = self.promotion() {
+            uci.push(match kind {
+                PieceKind::Queen => 'q',
+                PieceKind::Rook => 'r',
+                PieceKind::Bishop => 'b',
+                _ => 'n',
+            });
+        }
+        uci
+    }
+
+    /**
+     * Parses a move in UCI coordinate notation against the legal moves of the given board.
+     *
+     * Promotions must name the promotion piece with a lowercase letter, as in "e7e8q".
+     *
+     * @param board - The board the move is played on, with up to date legal moves.
+     * @param uci - The move in UCI coordinate notation, such as "e2e4", "e7e8q" or "e1g1".
+     * @return The legal move described by the string, or the reason it was rejected.
+     */
+    pub fn from_uci(board: &Board, uci: &str) -> Result<Self, UciMoveError> {
+        let uci = uci.trim();
+        let malformed = || UciMoveError::Malformed(uci.to_string());
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(malformed());
+        }
+        let from = pos_from_algebraic(&uci[0..2]).ok_or_else(malformed)?;
+        let to = pos_from_algebraic(&uci[2..4]).ok_or_else(malformed)?;
+        let promotion = match &uci[4..] {
+            "" => None,
+            "q" => Some(PieceKind::Queen),
+            "r" => Some(PieceKind::Rook),
+            "b" => Some(PieceKind::Bishop),
+            "n" => Some(PieceKind::Knight),
+            _ => return Err(malformed()),
+        };
+
+        board
+            .board_info
+            .valid_moves
+            .iter()
+            .find(|m| m.from == from && m.to == to && m.promotion() == promotion)
+            .cloned()
+            .ok_or_else(|| UciMoveError::Illegal(uci.to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::board::piece::{get_moves, Piece, PieceKind};
-    use crate::board::{display_board, idx, in_bounds, Square};
+    use crate::board::{display_board, idx, in_bounds, Board};
     use crate::game::player::Color;
-    use crate::game::{apply_move, get_color_moves, Game, undo_move};
-    use crate::rules::r#move::Move;
+    use crate::game::{apply_move, get_color_moves, Game};
+    use crate::rules::perft::{divide, perft};
+    use crate::rules::r#move::{Move, MoveType, UciMoveError};
 
     fn display_moves(game: &Game, moves: &[Move]) {
         for mv in moves.iter() {
-            let mut gs = game.clone();
-            let result= apply_move(gs, mv.from, mv.to);
+            let gs = game.clone();
+            let result = apply_move(gs, mv.from, mv.to);
             if let Ok(gs) = result {
                 println!("----------------------------------");
                 println!("Move: {:}", mv);
@@ -174,34 +276,24 @@ mod tests {
     /**
      * Performs a recursive move generation test.
      *
-     * This function recursively generates and tests moves for the specified game up to the given depth.
-     * It counts the number of positions evaluated during the test.
+     * This function counts the positions of the legal move tree below the specified game up to the given depth.
+     * If the count does not match the expected value, it prints the count below every root move, which can be
+     * compared with a trusted move generator to find the move that goes wrong.
      *
      * @param game - The game for which moves are to be generated and tested.
      * @param depth - The maximum depth of recursion for move generation and testing.
+     * @param expected - The expected number of positions.
      *
      * @return The number of positions evaluated during the test.
      */
     fn recursive_mvgen_test(game: &Game, depth: usize, expected: usize) -> usize {
-        if depth == 0 {
-            return 1;
-        }
-
-        let mut num_positions = 0usize;
-        let moves = game.board.get_current_moves();
-        let mut new_game = game.clone();
-
-        for mv in moves.iter() {
-            let result = apply_move(new_game.clone(), mv.from, mv.to);
-            if result.is_ok() {
-                let mut new_game = result.unwrap();
-                num_positions += recursive_mvgen_test(&mut new_game, depth - 1, expected);
-            }
-        }
+        let num_positions = perft(&game.board, depth as u32).nodes as usize;
 
         if num_positions != expected {
             println!("Expected: {}, Actual: {}", expected, num_positions);
-            display_moves(&mut new_game, &moves);
+            for (m, stats) in divide(&game.board, depth as u32) {
+                println!("{}: {}", m.to_uci(), stats.nodes);
+            }
         }
 
         num_positions
@@ -220,6 +312,7 @@ mod tests {
      */
     fn queen_scenario(game: &mut Game, pos: (u8, u8), expected: usize, color: Color) {
         let queen = game.board.get_piece(pos).unwrap();
+        game.board.update();
         game.board.board_info.valid_moves = get_color_moves(&game.board, color);
         let moves = get_moves(&game.board.board_info, &queen);
 
@@ -266,21 +359,6 @@ mod tests {
         game_with_piece_at(pos, color, PieceKind::Queen)
     }
 
-    /**
-     * Places a piece on the specified square.
-     *
-     * This function adds a new piece of the specified kind and color to the given square array at the
-     * specified position.
-     *
-     * @param sq - The array of squares to place the piece on.
-     * @param pos - The position of the square where the piece is to be placed.
-     * @param color - The color of the piece.
-     * @param kind - The kind of the piece.
-     */
-    fn place_piece(sq: &mut [Square; 64], pos: (u8, u8), color: Color, kind: PieceKind) {
-        sq[idx(pos)] = Some(Piece::new(kind, pos, color));
-    }
-
     fn scattered_surround_by(
         gs: &mut Game,
         pos: (u8, u8),
@@ -461,6 +539,7 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "too slow for the default test run"]
     /**
      * Test the move generation algorithm for a specific depth.
      *
@@ -479,6 +558,7 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "too slow for the default test run"]
     /**
      * Test function to generate and count all possible positions up to the given depth.
      *
@@ -493,6 +573,7 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "too slow for the default test run"]
     /**
      * Test case for move generation using a maximum recursion depth of 7.
      *
@@ -508,6 +589,7 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "too slow for the default test run"]
     /**
      * Test move generation for a specific depth.
      *
@@ -525,6 +607,7 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "too slow for the default test run"]
     /**
      * Test move generation for a specific depth.
      *
@@ -540,4 +623,55 @@ mod tests {
         let num_positions = recursive_mvgen_test(&game, 9, expected);
         assert_eq!(num_positions, expected);
     }
+
+    #[test]
+    /**
+     * Tests that UCI coordinate notation is parsed into the matching legal move and written back unchanged.
+     */
+    fn test_uci_moves() {
+        let board = Board::new_from_fen("r3k3/1P6/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+        let cases = [
+            (
+                "e1g1",
+                MoveType::Castle(crate::rules::r#move::CastleType::KingSide),
+            ),
+            ("a1a8", MoveType::Capture),
+            ("b7b8q", MoveType::Promotion(PieceKind::Queen)),
+            ("b7a8n", MoveType::PromotionCapture(PieceKind::Knight)),
+            ("h1h5", MoveType::Normal),
+        ];
+        for (uci, move_type) in cases {
+            let m = Move::from_uci(&board, uci).unwrap();
+            assert_eq!(m.move_type, move_type);
+            assert_eq!(m.to_uci(), uci);
+        }
+
+        for m in &board.board_info.valid_moves {
+            assert_eq!(Move::from_uci(&board, &m.to_uci()).as_ref(), Ok(m));
+        }
+    }
+
+    #[test]
+    /**
+     * Tests that malformed and illegal UCI moves are rejected.
+     */
+    fn test_uci_move_errors() {
+        let board = Board::new_from_fen("r3k3/1P6/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+        for uci in ["", "e2", "e2-e4", "i1a1", "a0a1", "b7b8k", "b7b8Q", "é2e4"] {
+            assert_eq!(
+                Move::from_uci(&board, uci),
+                Err(UciMoveError::Malformed(uci.to_string())),
+                "{}",
+                uci
+            );
+        }
+        for uci in ["e1c1", "b7b8", "a1b2", "e2e4"] {
+            assert_eq!(
+                Move::from_uci(&board, uci),
+                Err(UciMoveError::Illegal(uci.to_string())),
+                "{}",
+                uci
+            );
+        }
+    }
 }
@@ -0,0 +1,370 @@
+use crate::board::board_info::BoardInfo;
+use crate::board::piece::{Piece, PieceKind};
+use crate::board::Position;
+use crate::rules::r#move::{CastleType, Move, MoveType};
+
+pub const MAX_MOVES: usize = 16 * 27; // A side has at most 16 pieces and no piece has more than 27 moves
+
+pub const QUIET: u16 = 0; // Move onto an empty square
+pub const DOUBLE_PAWN_PUSH: u16 = 1; // Pawn move of two squares from its starting rank
+pub const KING_CASTLE: u16 = 2; // Castling on the king side
+pub const QUEEN_CASTLE: u16 = 3; // Castling on the queen side
+pub const CAPTURE: u16 = 4; // Move onto a square occupied by an enemy piece
+pub const EN_PASSANT: u16 = 5; // Pawn capture onto the en passant target square
+pub const PROMOTION: u16 = 8; // Promotion without a capture, plus the index of the promotion kind
+pub const PROMOTION_CAPTURE: u16 = 12; // Promotion with a capture, plus the index of the promotion kind
+
+// Piece kinds a pawn can promote to, in the order of their index in the move flags
+const PROMOTION_KINDS: [PieceKind; 4] = [
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+];
+
+// Struct to represent a move packed into 16 bits: the from square, the to square and 4 bits of flags
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PackedMove(u16);
+
+impl PackedMove {
+    /**
+     * Creates a packed move.
+     *
+     * @param from - The index of the square the piece moves from.
+     * @param to - The index of the square the piece moves to.
+     * @param flags - The kind of move, one of the flag constants.
+     * @return The packed move.
+     */
+    pub fn new(from: usize, to: usize, flags: u16) -> Self {
+        Self(from as u16 | (to as u16) << 6 | flags << 12)
+    }
+
+    /**
+     * Creates a promotion, with or without a capture.
+     *
+     * @param from - The index of the square the pawn moves from.
+     * @param to - The index of the square the pawn promotes on.
+     * @param kind - The piece kind the pawn promotes to.
+     * @param capture - Whether the promotion captures a piece.
+     * @return The packed move.
+     */
+    pub fn promotion(from: usize, to: usize, kind: PieceKind, capture: bool) -> Self {
+        let base = if capture {
+            PROMOTION_CAPTURE
+        } else {
+            PROMOTION
+        };
+        let index = PROMOTION_KINDS.iter().position(|&k| k == kind).unwrap_or(3);
+        Self::new(from, to, base + index as u16)
+    }
+
+    /**
+     * Returns the index of the square the piece moves from.
+     *
+     * @return The square index, from 0 for a1 to 63 for h8.
+     */
+    pub fn from_square(self) -> usize {
+        (self.0 & 0x3f) as usize
+    }
+
+    /**
+     * Returns the index of the square the piece moves to.
+     *
+     * @return The square index, from 0 for a1 to 63 for h8.
+     */
+    pub fn to_square(self) -> usize {
+        (self.0 >> 6 & 0x3f) as usize
+    }
+
+    /**
+     * Returns the flags telling what kind of move this is.
+     *
+     * @return One of the flag constants, with the promotion kind index added for promotions.
+     */
+    pub fn flags(self) -> u16 {
+        self.0 >> 12
+    }
+
+    /**
+     * Returns the position the piece moves from.
+     *
+     * @return The position of the from square.
+     */
+    pub fn from_pos(self) -> Position {
+        square_position(self.from_square())
+    }
+
+    /**
+     * Returns the position the piece moves to.
+     *
+     * @return The position of the to square.
+     */
+    pub fn to_pos(self) -> Position {
+        square_position(self.to_square())
+    }
+
+    /**
+     * Checks if the move captures a piece, including en passant and promotion captures.
+     *
+     * @return true if the move is a capture, false otherwise.
+     */
+    pub fn is_capture(self) -> bool {
+        self.flags() & CAPTURE != 0
+    }
+
+    /**
+     * Checks if the move captures en passant.
+     *
+     * @return true if the move is an en passant capture, false otherwise.
+     */
+    pub fn is_en_passant(self) -> bool {
+        self.flags() == EN_PASSANT
+    }
+
+    /**
+     * Checks if the move castles to either side.
+     *
+     * @return true if the move is a castling move, false otherwise.
+     */
+    pub fn is_castle(self) -> bool {
+        matches!(self.flags(), KING_CASTLE | QUEEN_CASTLE)
+    }
+
+    /**
+     * Returns the piece kind the move promotes to.
+     *
+     * @return The promotion piece kind, or None if the move is not a promotion.
+     */
+    pub fn promotion_kind(self) -> Option<PieceKind> {
+        (self.flags() & PROMOTION != 0).then(|| PROMOTION_KINDS[(self.flags() & 3) as usize])
+    }
+
+    /**
+     * Decodes the kind of move from the flags.
+     *
+     * @return The move type of the move.
+     */
+    pub fn move_type(self) -> MoveType {
+        match self.flags() {
+            DOUBLE_PAWN_PUSH => MoveType::DoublePawnPush,
+            KING_CASTLE => MoveType::Castle(CastleType::KingSide),
+            QUEEN_CASTLE => MoveType::Castle(CastleType::QueenSide),
+            CAPTURE => MoveType::Capture,
+            EN_PASSANT => MoveType::EnPassant,
+            flags if flags >= PROMOTION_CAPTURE => {
+                MoveType::PromotionCapture(PROMOTION_KINDS[(flags & 3) as usize])
+            }
+            flags if flags >= PROMOTION => {
+                MoveType::Promotion(PROMOTION_KINDS[(flags & 3) as usize])
+            }
+            _ => MoveType::Normal,
+        }
+    }
+
+    /**
+     * Expands the packed move into a full move of the given piece.
+     *
+     * @param piece - The piece making the move, standing on the from square.
+     * @return The full move.
+     */
+    pub fn with_piece(self, piece: Piece) -> Move {
+        Move::new(piece, self.to_pos(), self.move_type(), piece.color)
+    }
+
+    /**
+     * Expands the packed move into a full move, taking the moving piece from the chessboard.
+     *
+     * @param board_info - The board information whose squares hold the moving piece.
+     * @return The full move.
+     */
+    pub fn to_move(self, board_info: &BoardInfo) -> Move {
+        let piece = board_info.squares[self.from_square()]
+            .expect("a packed move starts on a square holding a piece");
+        self.with_piece(piece)
+    }
+}
+
+impl From<&Move> for PackedMove {
+    /**
+     * Packs a full move into 16 bits, dropping the moving piece and its color.
+     *
+     * @param m - The move to pack.
+     * @return The packed move.
+     */
+    fn from(m: &Move) -> Self {
+        let from = (m.from.1 * 8 + m.from.0) as usize;
+        let to = (m.to.1 * 8 + m.to.0) as usize;
+        match m.move_type {
+            MoveType::Normal => Self::new(from, to, QUIET),
+            MoveType::DoublePawnPush => Self::new(from, to, DOUBLE_PAWN_PUSH),
+            MoveType::Capture => Self::new(from, to, CAPTURE),
+            MoveType::Castle(CastleType::KingSide) => Self::new(from, to, KING_CASTLE),
+            MoveType::Castle(CastleType::QueenSide) => Self::new(from, to, QUEEN_CASTLE),
+            MoveType::EnPassant => Self::new(from, to, EN_PASSANT),
+            MoveType::Promotion(kind) => Self::promotion(from, to, kind, false),
+            MoveType::PromotionCapture(kind) => Self::promotion(from, to, kind, true),
+        }
+    }
+}
+
+/**
+ * Converts a square index to its position.
+ *
+ * @param square - The square index, from 0 for a1 to 63 for h8.
+ * @return The position of the square.
+ */
+fn square_position(square: usize) -> Position {
+    ((square % 8) as u8, (square / 8) as u8)
+}
+
+// Struct to hold the moves of a position on the stack, without allocating
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [PackedMove; MAX_MOVES], // Storage for the moves, of which the first len are used
+    len: usize,                     // Number of moves in the list
+}
+
+impl Default for MoveList {
+    /**
+     * Creates an empty move list.
+     *
+     * @return The empty move list.
+     */
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveList {
+    /**
+     * Creates an empty move list.
+     *
+     * @return The empty move list.
+     */
+    pub fn new() -> Self {
+        Self {
+            moves: [PackedMove::default(); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /**
+     * Adds a move to the end of the list.
+     *
+     * @param m - The move to add.
+     */
+    pub fn push(&mut self, m: PackedMove) {
+        self.moves[self.len] = m;
+        self.len += 1;
+    }
+
+    /**
+     * Returns the number of moves in the list.
+     *
+     * @return The number of moves.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Checks if the list holds no moves.
+     *
+     * @return true if the list is empty, false otherwise.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Returns the moves in the list.
+     *
+     * @return The slice of moves, in the order they were added.
+     */
+    pub fn as_slice(&self) -> &[PackedMove] {
+        &self.moves[..self.len]
+    }
+
+    /**
+     * Returns the moves in the list for changing them in place, such as for sorting.
+     *
+     * @return The mutable slice of moves, in the order they were added.
+     */
+    pub fn as_mut_slice(&mut self) -> &mut [PackedMove] {
+        &mut self.moves[..self.len]
+    }
+
+    /**
+     * Returns an iterator over the moves in the list.
+     *
+     * @return The iterator, in the order the moves were added.
+     */
+    pub fn iter(&self) -> std::slice::Iter<'_, PackedMove> {
+        self.as_slice().iter()
+    }
+
+    /**
+     * Expands every move of the list into a full move.
+     *
+     * @param board_info - The board information whose squares hold the moving pieces.
+     * @return The full moves, in the order they were added.
+     */
+    pub fn to_moves(&self, board_info: &BoardInfo) -> Vec<Move> {
+        self.iter().map(|m| m.to_move(board_info)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::piece::PieceKind;
+    use crate::board::Board;
+    use crate::rules::move_list::{MoveList, PackedMove, CAPTURE, EN_PASSANT, MAX_MOVES};
+    use crate::rules::r#move::MoveType;
+
+    #[test]
+    /**
+     * Tests that packing and expanding every legal move of a few positions gives back the same move.
+     */
+    fn test_packed_move_round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let board = Board::new_from_fen(fen).unwrap();
+            for m in &board.board_info.valid_moves {
+                let packed = PackedMove::from(m);
+                assert_eq!(packed.to_move(&board.board_info), *m);
+                assert_eq!(packed.is_capture(), m.is_capture());
+                assert_eq!(packed.promotion_kind(), m.promotion());
+            }
+        }
+
+        let packed = PackedMove::promotion(52, 61, PieceKind::Knight, true);
+        assert_eq!(packed.from_pos(), (4, 6));
+        assert_eq!(packed.to_pos(), (5, 7));
+        assert_eq!(
+            packed.move_type(),
+            MoveType::PromotionCapture(PieceKind::Knight)
+        );
+        assert!(packed.is_capture());
+        assert!(PackedMove::new(36, 43, EN_PASSANT).is_capture());
+        assert!(!PackedMove::new(36, 43, EN_PASSANT).is_castle());
+        assert_eq!(std::mem::size_of::<PackedMove>(), 2);
+    }
+
+    #[test]
+    /**
+     * Tests adding moves to a move list and reading them back.
+     */
+    fn test_move_list() {
+        let mut list = MoveList::new();
+        assert!(list.is_empty());
+        for i in 0..MAX_MOVES {
+            list.push(PackedMove::new(i % 64, (i + 1) % 64, CAPTURE));
+        }
+        assert_eq!(list.len(), MAX_MOVES);
+        assert_eq!(list.as_slice()[65].from_square(), 1);
+        assert!(list.iter().all(|m| m.is_capture()));
+    }
+}
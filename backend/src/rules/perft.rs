@@ -0,0 +1,329 @@
+use crate::board::board_info::BoardInfo;
+use crate::board::{Board, FenError};
+use crate::game::player::from_idx;
+use crate::rules::generate_legal_moves;
+use crate::rules::move_list::{MoveList, PackedMove};
+use crate::rules::r#move::Move;
+use std::ops::AddAssign;
+use std::time::Instant;
+
+// Struct to hold the counts of a perft run, as listed in the usual perft reference tables
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PerftStats {
+    pub nodes: u64,       // Leaf positions at the full depth
+    pub captures: u64,    // Leaf moves capturing a piece, including en passant
+    pub en_passants: u64, // Leaf moves capturing en passant
+    pub castles: u64,     // Leaf moves castling
+    pub promotions: u64,  // Leaf moves promoting a pawn
+    pub checks: u64,      // Leaf moves giving check
+    pub checkmates: u64,  // Leaf moves giving checkmate
+}
+
+impl AddAssign for PerftStats {
+    /**
+     * Adds the counts of another perft run to these counts.
+     *
+     * @param other - The counts to add.
+     */
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+/**
+ * Counts the leaf positions of the legal move tree below the given board, and what kind of moves lead to them.
+ *
+ * @param board - The board to count the positions from.
+ * @param depth - The number of plies to play.
+ * @return The counts of the leaf positions and the moves leading to them.
+ */
+pub fn perft(board: &Board, depth: u32) -> PerftStats {
+    let mut board = board.clone();
+    board.update_bitboards();
+    count(&mut board, depth)
+}
+
+/**
+ * Counts the leaf positions below every legal move of the given board separately.
+ *
+ * Comparing these counts with those of a trusted move generator points to the move below which the move
+ * generation goes wrong.
+ *
+ * @param board - The board to count the positions from, with up to date legal moves.
+ * @param depth - The number of plies to play, at least 1.
+ * @return Every legal move with the counts of the positions below it.
+ */
+pub fn divide(board: &Board, depth: u32) -> Vec<(Move, PerftStats)> {
+    let mut board = board.clone();
+    board.update_bitboards();
+    let moves = board.board_info.valid_moves.clone();
+    moves
+        .into_iter()
+        .map(|m| {
+            let stats = count_move(&mut board, PackedMove::from(&m), depth);
+            (m, stats)
+        })
+        .collect()
+}
+
+/**
+ * Counts the leaf positions below a board by generating its legal moves into a move list.
+ *
+ * @param board - The board to count the positions from, with up to date bitboards.
+ * @param depth - The number of plies to play.
+ * @return The counts of the leaf positions and the moves leading to them.
+ */
+fn count(board: &mut Board, depth: u32) -> PerftStats {
+    if depth == 0 {
+        return PerftStats {
+            nodes: 1,
+            ..PerftStats::default()
+        };
+    }
+    let mut moves = MoveList::new();
+    generate_legal_moves(
+        &board.board_info,
+        from_idx(board.board_info.turn),
+        &mut moves,
+    );
+    let mut stats = PerftStats::default();
+    for &m in moves.iter() {
+        stats += count_move(board, m, depth);
+    }
+    stats
+}
+
+/**
+ * Plays a move, counts the leaf positions below it and takes the move back.
+ *
+ * @param board - The board to play the move on, with up to date bitboards, which it has again afterwards.
+ * @param m - The legal move to play.
+ * @param depth - The number of plies to play, including the move, at least 1.
+ * @return The counts of the positions below the move.
+ */
+fn count_move(board: &mut Board, m: PackedMove, depth: u32) -> PerftStats {
+    board.make_move(m.to_move(&board.board_info));
+    let stats = if depth <= 1 {
+        leaf_stats(&board.board_info, m)
+    } else {
+        count(board, depth - 1)
+    };
+    board.undo_move();
+    stats
+}
+
+/**
+ * Counts a move leading to a leaf position.
+ *
+ * Only positions with the king in check need their legal moves, to tell check from checkmate.
+ *
+ * @param board_info - The board information after the move, with up to date bitboards.
+ * @param m - The move leading to the leaf position.
+ * @return The counts of the single leaf position.
+ */
+fn leaf_stats(board_info: &BoardInfo, m: PackedMove) -> PerftStats {
+    let to_move = from_idx(board_info.turn);
+    let check = board_info.checkers != 0;
+    let checkmate = check && {
+        let mut moves = MoveList::new();
+        generate_legal_moves(board_info, to_move, &mut moves);
+        moves.is_empty()
+    };
+    PerftStats {
+        nodes: 1,
+        captures: m.is_capture() as u64,
+        en_passants: m.is_en_passant() as u64,
+        castles: m.is_castle() as u64,
+        promotions: m.promotion_kind().is_some() as u64,
+        checks: check as u64,
+        checkmates: checkmate as u64,
+    }
+}
+
+/**
+ * Runs perft on a position and prints the divide counts, the totals and the speed.
+ *
+ * The root moves are printed in UCI notation in alphabetical order, as `e2e4: 9328`, so the output can be
+ * compared line by line with other engines.
+ *
+ * @param fen - The position to count from, as a FEN string.
+ * @param depth - The number of plies to play, at least 1.
+ * @return The totals of the perft run, or the reason the FEN string was rejected.
+ */
+pub fn run(fen: &str, depth: u32) -> Result<PerftStats, FenError> {
+    let board = Board::new_from_fen(fen)?;
+    let started = Instant::now();
+    let mut moves: Vec<(String, PerftStats)> = divide(&board, depth.max(1))
+        .into_iter()
+        .map(|(m, stats)| (m.to_uci(), stats))
+        .collect();
+    let elapsed = started.elapsed();
+    moves.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut total = PerftStats::default();
+    for (uci, stats) in moves {
+        println!("{}: {}", uci, stats.nodes);
+        total += stats;
+    }
+    let nps = (total.nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64;
+    println!();
+    println!("Nodes:       {}", total.nodes);
+    println!("Captures:    {}", total.captures);
+    println!("En passant:  {}", total.en_passants);
+    println!("Castles:     {}", total.castles);
+    println!("Promotions:  {}", total.promotions);
+    println!("Checks:      {}", total.checks);
+    println!("Checkmates:  {}", total.checkmates);
+    println!("Time:        {} ms", elapsed.as_millis());
+    println!("Nodes/s:     {}", nps);
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, FenError};
+    use crate::game::player::Color::White;
+    use crate::rules::perft::{divide, perft, run, PerftStats};
+
+    const PERFT_SUITE: &str = include_str!("perft.epd"); // Reference positions with their perft counts
+    const QUICK_SUITE_NODES: u64 = 10_000; // Largest count checked in the default test run
+
+    /**
+     * Parses a line of the perft suite.
+     *
+     * @param line - A line holding a FEN string followed by `;D<depth> <nodes>` entries.
+     * @return The FEN string and the expected node count per depth, or None for blank lines and comments.
+     */
+    fn parse_epd_line(line: &str) -> Option<(&str, Vec<(u32, u64)>)> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let mut fields = line.split(';');
+        let fen = fields.next()?.trim();
+        let counts = fields
+            .map(|field| {
+                let (depth, nodes) = field
+                    .trim()
+                    .trim_start_matches('D')
+                    .split_once(' ')
+                    .unwrap();
+                (depth.parse().unwrap(), nodes.parse().unwrap())
+            })
+            .collect();
+        Some((fen, counts))
+    }
+
+    /**
+     * Runs perft on every position of the suite and compares the counts with the reference counts.
+     *
+     * @param max_nodes - The largest reference count to check; deeper counts are skipped.
+     */
+    fn check_perft_suite(max_nodes: u64) {
+        let lines: Vec<_> = PERFT_SUITE.lines().filter_map(parse_epd_line).collect();
+        assert_eq!(lines.len(), 263);
+        for (fen, counts) in lines {
+            let board = Board::new_from_fen(fen).unwrap();
+            for (depth, nodes) in counts.into_iter().filter(|(_, nodes)| *nodes <= max_nodes) {
+                assert_eq!(
+                    perft(&board, depth).nodes,
+                    nodes,
+                    "{} at depth {}",
+                    fen,
+                    depth
+                );
+            }
+        }
+    }
+
+    #[test]
+    /**
+     * Tests the shallow depths of the perft suite.
+     */
+    fn test_perft_suite() {
+        check_perft_suite(QUICK_SUITE_NODES);
+    }
+
+    #[test]
+    #[ignore = "too slow for the default test run"]
+    /**
+     * Tests every depth of the perft suite, up to millions of positions each.
+     */
+    fn test_perft_suite_deep() {
+        check_perft_suite(u64::MAX);
+    }
+
+    #[test]
+    /**
+     * Tests the perft counts of the starting position against the reference numbers.
+     */
+    fn test_perft_start_position() {
+        let board = Board::new_standard();
+        assert_eq!(perft(&board, 0).nodes, 1);
+        assert_eq!(
+            perft(&board, 3),
+            PerftStats {
+                nodes: 8902,
+                captures: 34,
+                en_passants: 0,
+                castles: 0,
+                promotions: 0,
+                checks: 12,
+                checkmates: 0,
+            }
+        );
+    }
+
+    #[test]
+    /**
+     * Tests that the position with the most known legal moves is counted, and that a position with more pieces
+     * than a side can have is rejected instead of overflowing the move list.
+     */
+    fn test_perft_move_list_limit() {
+        let board =
+            Board::new_from_fen("R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1").unwrap();
+        assert_eq!(perft(&board, 1).nodes, 218);
+
+        assert_eq!(
+            run("QQQQQQQk/Q6Q/Q6Q/Q6Q/Q6Q/Q6Q/Q6Q/KQQQQQQQ w - - 0 1", 1),
+            Err(FenError::TooManyPieces(White))
+        );
+    }
+
+    #[test]
+    /**
+     * Tests the perft counts of a position full of castling, en passant and captures against the reference
+     * numbers, and that the divide counts add up to them.
+     */
+    fn test_perft_kiwipete() {
+        let board = Board::new_from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let stats = perft(&board, 2);
+        assert_eq!(
+            stats,
+            PerftStats {
+                nodes: 2039,
+                captures: 351,
+                en_passants: 1,
+                castles: 91,
+                promotions: 0,
+                checks: 3,
+                checkmates: 0,
+            }
+        );
+
+        let moves = divide(&board, 2);
+        assert_eq!(moves.len(), 48);
+        assert_eq!(moves.iter().map(|(_, s)| s.nodes).sum::<u64>(), 2039);
+        let castle = moves.iter().find(|(m, _)| m.to_uci() == "e1g1").unwrap();
+        assert_eq!(castle.1.nodes, 43);
+    }
+}
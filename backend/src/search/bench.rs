@@ -0,0 +1,117 @@
+use crate::board::Board;
+use crate::search::tt::TranspositionTable;
+use crate::search::{run_search, SearchLimits};
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+pub const DEFAULT_BENCH_DEPTH: u32 = 3; // Depth searched by the benchmark unless another is given
+
+// Positions searched by the benchmark, from the opening to the endgame
+const BENCH_POSITIONS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+// Struct to hold the node counts of one benchmark position
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchResult {
+    pub fen: String,    // Position searched
+    pub unordered: u64, // Positions visited with moves in generated order
+    pub ordered: u64,   // Positions visited with ordered moves
+}
+
+/**
+ * Searches a position to a fixed depth with and without move ordering and counts the positions visited.
+ *
+ * Both searches start with an empty transposition table, so only the move ordering differs.
+ *
+ * @param board - The position to search, with up to date legal moves.
+ * @param depth - The depth to search to.
+ * @return The number of positions visited without and with move ordering.
+ */
+pub fn compare(board: &Board, depth: u32) -> (u64, u64) {
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..SearchLimits::default()
+    };
+    let stop = AtomicBool::new(false);
+    let [unordered, ordered] = [false, true].map(|ordering| {
+        let mut tt = TranspositionTable::default();
+        run_search(board, &limits, &stop, None, &mut tt, ordering).nodes
+    });
+    (unordered, ordered)
+}
+
+/**
+ * Runs the benchmark positions and prints the positions visited with and without move ordering.
+ *
+ * @param depth - The depth to search every position to.
+ * @return The node counts of every benchmark position.
+ */
+pub fn run(depth: u32) -> Vec<BenchResult> {
+    let started = Instant::now();
+    let mut results = Vec::new();
+    println!(
+        "{:>12} {:>12} {:>8}  position",
+        "unordered", "ordered", "saved"
+    );
+    for fen in BENCH_POSITIONS {
+        let board = Board::new_from_fen(fen).unwrap();
+        let (unordered, ordered) = compare(&board, depth);
+        println!(
+            "{:>12} {:>12} {:>7}%  {}",
+            unordered,
+            ordered,
+            saved_percent(unordered, ordered),
+            fen
+        );
+        results.push(BenchResult {
+            fen: fen.to_string(),
+            unordered,
+            ordered,
+        });
+    }
+
+    let unordered = results.iter().map(|result| result.unordered).sum();
+    let ordered = results.iter().map(|result| result.ordered).sum();
+    println!(
+        "{:>12} {:>12} {:>7}%  total at depth {} in {:.1}s",
+        unordered,
+        ordered,
+        saved_percent(unordered, ordered),
+        depth,
+        started.elapsed().as_secs_f64()
+    );
+    results
+}
+
+/**
+ * Returns the share of positions the move ordering saves.
+ *
+ * @param unordered - The positions visited without move ordering.
+ * @param ordered - The positions visited with move ordering.
+ * @return The positions saved in percent of the unordered count.
+ */
+fn saved_percent(unordered: u64, ordered: u64) -> i64 {
+    100 - (ordered * 100 / unordered.max(1)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::search::bench::compare;
+
+    #[test]
+    /**
+     * Tests that move ordering visits fewer positions than searching moves in generated order.
+     */
+    fn test_ordering_saves_nodes() {
+        let board =
+            Board::new_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let (unordered, ordered) = compare(&board, 2);
+        assert!(ordered < unordered);
+    }
+}
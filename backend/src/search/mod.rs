@@ -0,0 +1,736 @@
+use crate::board::piece::PieceKind;
+use crate::board::Board;
+use crate::eval::{evaluate, piece_value};
+use crate::game::player::{from_idx, Color};
+use crate::rules::move_list::{MoveList, PackedMove};
+use crate::rules::r#move::Move;
+use crate::rules::{generate_legal_moves, is_insufficient_material};
+use crate::search::ordering::{victim, Heuristics};
+use crate::search::time::TimeBudget;
+use crate::search::tt::{score_from_tt, Bound, TranspositionTable};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub mod bench;
+pub mod ordering;
+pub mod time;
+pub mod tt;
+
+// Function called with the result of every completed search iteration
+pub type Reporter = Arc<dyn Fn(&SearchResult) + Send + Sync>;
+
+pub const MATE: i32 = 32000; // Score of delivering checkmate right now
+pub const INFINITY: i32 = 32001; // Bound above every possible score
+pub const MAX_PLY: usize = 64; // Deepest ply the search can reach
+const DELTA_MARGIN: i32 = 200; // Positional gain a capture may add to the material it wins
+
+// Struct to hold the limits a search has to respect
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,         // Maximum depth in plies
+    pub movetime: Option<Duration>, // Exact time to spend on the move
+    pub wtime: Option<Duration>,    // Time left on White's clock
+    pub btime: Option<Duration>,    // Time left on Black's clock
+    pub winc: Duration,             // White's increment per move
+    pub binc: Duration,             // Black's increment per move
+    pub movestogo: Option<u32>,     // Moves left until the next time control
+    pub infinite: bool,             // Search until told to stop
+    pub move_overhead: Duration,    // Time kept in reserve for communication delays
+}
+
+impl SearchLimits {
+    /**
+     * Returns the time the given player may spend on the current move.
+     *
+     * A fixed move time is used as is. Otherwise the time is allocated from the player's clock, increment and
+     * moves to go. The move overhead is kept in reserve in both cases.
+     *
+     * @param color - The player to move.
+     * @return The soft and hard time limits of the move, or None if the search is not limited by time.
+     */
+    pub fn time_budget(&self, color: Color) -> Option<TimeBudget> {
+        let overhead = self.move_overhead;
+        if self.infinite {
+            return None;
+        }
+        if let Some(movetime) = self.movetime {
+            return Some(TimeBudget::fixed(movetime.saturating_sub(overhead)));
+        }
+        let (time, increment) = match color {
+            Color::White => (self.wtime?, self.winc),
+            Color::Black => (self.btime?, self.binc),
+        };
+        Some(TimeBudget::allocate(
+            time,
+            increment,
+            self.movestogo,
+            overhead,
+        ))
+    }
+}
+
+// Struct to hold the outcome of a search
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>, // Best move found, or None if there are no legal moves
+    pub score: i32,              // Score in centipawns from the point of view of the player to move
+    pub depth: u32,              // Depth of the last completed iteration
+    pub nodes: u64,              // Positions visited
+    pub pv: Vec<Move>,           // Principal variation, starting with the best move
+    pub hashfull: u32,           // Permille of the transposition table in use
+}
+
+impl SearchResult {
+    /**
+     * Returns the number of moves until checkmate if the score is a mate score.
+     *
+     * @return The number of moves until the player to move mates, negative if the player to move gets mated,
+     *         or None if the score is not a mate score.
+     */
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_in(self.score)
+    }
+}
+
+/**
+ * Returns the number of moves until checkmate if the given score is a mate score.
+ *
+ * Mate scores count down from `MATE` by one per ply, so mating sooner scores higher.
+ *
+ * @param score - The score from the point of view of the player to move.
+ * @return The number of moves until the player to move mates, negative if the player to move gets mated,
+ *         or None if the score is not a mate score.
+ */
+pub fn mate_in(score: i32) -> Option<i32> {
+    let plies = MATE - score.abs();
+    if plies > MAX_PLY as i32 {
+        None
+    } else if score > 0 {
+        Some((plies + 1) / 2)
+    } else {
+        Some(-(plies / 2))
+    }
+}
+
+// Struct to hold the state of a running search
+struct Searcher<'a> {
+    stop: &'a AtomicBool,           // Set from outside to end the search
+    deadline: Option<Instant>,      // When the search has to end
+    nodes: u64,                     // Positions visited so far
+    iteration: u32,                 // Depth of the current iteration
+    aborted: bool,                  // Whether the current iteration was cut short
+    pv: Vec<Vec<PackedMove>>,       // Principal variation found at each ply
+    pv_move: Option<PackedMove>,    // Best move of the previous iteration, searched first
+    tt: &'a mut TranspositionTable, // Positions searched so far, in this and earlier searches
+    heuristics: Heuristics,         // Killer moves, history and counter moves learned so far
+    ordering: bool,                 // Whether moves are ordered, or searched in generated order
+    keys: Vec<u64>, // Zobrist keys of the positions before the current one, in the game and search
+}
+
+impl Searcher<'_> {
+    /**
+     * Checks if the search has to end because it was stopped or ran out of time.
+     *
+     * The first iteration is never cut short, so that there is always a searched move to play.
+     *
+     * @return true if the search has to end, false otherwise.
+     */
+    fn should_stop(&mut self) -> bool {
+        if !self.aborted && self.iteration > 1 && self.nodes.is_multiple_of(256) {
+            self.aborted = self.should_stop_now();
+        }
+        self.aborted
+    }
+
+    /**
+     * Checks the clock and the stop flag without waiting for the next node check.
+     *
+     * @return true if the search was stopped or ran out of time, false otherwise.
+     */
+    fn should_stop_now(&self) -> bool {
+        let out_of_time = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        out_of_time || self.stop.load(Ordering::Relaxed)
+    }
+
+    /**
+     * Checks if the position repeats an earlier position of the game or of the current search path.
+     *
+     * Only positions since the last capture or pawn move with the same player to move can repeat.
+     *
+     * @param board - The position to check.
+     * @return true if the position occurred before, false otherwise.
+     */
+    fn is_repetition(&self, board: &Board) -> bool {
+        let key = board.board_info.zobrist_key;
+        self.keys
+            .iter()
+            .rev()
+            .take(board.board_info.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|&k| k == key)
+    }
+
+    /**
+     * Searches the position with negamax and alpha-beta pruning.
+     *
+     * Scores are from the point of view of the player to move. Being checkmated scores `-MATE` plus the
+     * distance in plies from the root, so faster mates are preferred and slower ones postponed. Positions drawn
+     * by the fifty-move rule or insufficient material score zero, and so do positions that repeat an earlier
+     * position, as the player who repeated could repeat again. Positions already searched deeply enough are
+     * scored from the transposition table. At the full depth the position is scored by the quiescence search.
+     *
+     * Moves are made and undone on the board, so it is left as it was.
+     *
+     * @param board - The position to search, with up to date bitboards.
+     * @param depth - The remaining depth in plies.
+     * @param ply - The distance from the root in plies.
+     * @param alpha - The score the player to move is already guaranteed.
+     * @param beta - The score the opponent is already guaranteed.
+     * @return The score of the position, or zero if the search was aborted.
+     */
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        self.pv[ply].clear();
+        if ply > 0 && (self.should_stop() || self.is_repetition(board)) {
+            return 0;
+        }
+
+        let mut moves = MoveList::new();
+        generate_legal_moves(
+            &board.board_info,
+            from_idx(board.board_info.turn),
+            &mut moves,
+        );
+        if moves.is_empty() {
+            return if board.board_info.checkers != 0 {
+                -MATE + ply as i32
+            } else {
+                0
+            };
+        }
+        if ply > 0
+            && (board.board_info.halfmove_clock >= 100 || is_insufficient_material(&board.squares))
+        {
+            return 0;
+        }
+        if depth == 0 || ply + 1 >= MAX_PLY {
+            return self.quiesce(board, ply, alpha, beta);
+        }
+
+        let key = board.board_info.zobrist_key;
+        let mut hash_move = None;
+        if let Some(entry) = self.tt.probe(key) {
+            hash_move = entry.best_move;
+            let score = score_from_tt(entry.score, ply);
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if ply > 0 && entry.depth >= depth && usable {
+                return score;
+            }
+        }
+
+        // The best move of the previous iteration, or else the stored best move, is searched first
+        let first = if ply == 0 {
+            self.pv_move.or(hash_move)
+        } else {
+            hash_move
+        };
+        if self.ordering {
+            self.heuristics.order(board, &mut moves, first, ply);
+        }
+
+        let original_alpha = alpha;
+        let mut best_move = None;
+        for &m in moves.iter() {
+            self.keys.push(key);
+            board.make_move(m.to_move(&board.board_info));
+            let score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha);
+            board.undo_move();
+            self.keys.pop();
+            if self.aborted {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(m);
+                let (line, rest) = self.pv.split_at_mut(ply + 1);
+                line[ply].clear();
+                line[ply].push(m);
+                line[ply].extend_from_slice(&rest[0]);
+                if alpha >= beta {
+                    if !m.is_capture() && m.promotion_kind().is_none() {
+                        self.heuristics.record_cutoff(board, m, depth, ply);
+                    }
+                    break;
+                }
+            }
+        }
+
+        let bound = if alpha >= beta {
+            Bound::Lower
+        } else if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(key, depth, alpha, bound, best_move, ply);
+        alpha
+    }
+
+    /**
+     * Searches captures and promotions until the position is quiet, so that the search does not stop in the
+     * middle of an exchange.
+     *
+     * The player to move may stand pat, taking the static evaluation instead of capturing. Captures that cannot
+     * raise the score to alpha even with a safety margin are skipped (delta pruning), and so are captures that
+     * lose material in the exchange that follows. A player in check may not stand pat and searches every evasion
+     * instead, as the check may cost more than the static evaluation shows.
+     *
+     * @param board - The position to search, with up to date bitboards.
+     * @param ply - The distance from the root in plies.
+     * @param alpha - The score the player to move is already guaranteed.
+     * @param beta - The score the opponent is already guaranteed.
+     * @return The score of the position, or zero if the search was aborted.
+     */
+    fn quiesce(&mut self, board: &mut Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        self.pv[ply].clear();
+        if self.should_stop() {
+            return 0;
+        }
+
+        let mut moves = MoveList::new();
+        generate_legal_moves(
+            &board.board_info,
+            from_idx(board.board_info.turn),
+            &mut moves,
+        );
+        if moves.is_empty() {
+            return if board.board_info.checkers != 0 {
+                -MATE + ply as i32
+            } else {
+                0
+            };
+        }
+        let in_check = board.board_info.checkers != 0;
+        let stand_pat = evaluate(board);
+        if (stand_pat >= beta && !in_check) || ply + 1 >= MAX_PLY {
+            return stand_pat;
+        }
+        if !in_check {
+            if stand_pat + piece_value(PieceKind::Queen) + DELTA_MARGIN < alpha {
+                return alpha;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut searched = MoveList::new();
+        for &m in moves.iter() {
+            if in_check || m.is_capture() || m.promotion_kind().is_some() {
+                searched.push(m);
+            }
+        }
+        if self.ordering {
+            self.heuristics.order(board, &mut searched, None, ply);
+        }
+        for &m in searched.iter() {
+            if !in_check && m.promotion_kind().is_none() {
+                let gain = victim(board, m).map_or(0, piece_value);
+                if stand_pat + gain + DELTA_MARGIN < alpha || board.board_info.see(m) < 0 {
+                    continue;
+                }
+            }
+            board.make_move(m.to_move(&board.board_info));
+            let score = -self.quiesce(board, ply + 1, -beta, -alpha);
+            board.undo_move();
+            if self.aborted {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        alpha
+    }
+}
+
+/**
+ * Searches the position for the best move.
+ *
+ * The search deepens one ply at a time until the depth limit is reached, the time budget is used up or the stop
+ * flag is set. Only completed iterations are used, except that the first iteration always completes so there is
+ * always a move to play. Every completed iteration is passed to the reporter.
+ *
+ * With a time budget, no new iteration is started once the soft limit has passed, and the running iteration is
+ * cut short at the hard limit. The soft limit is extended while the best move changes or the score falls.
+ *
+ * The Zobrist keys of the positions played before the searched one are taken from the board's undo stack, so
+ * moves that repeat a position of the game score as a draw.
+ *
+ * @param board - The position to search, with up to date legal moves and the moves that led to it.
+ * @param limits - The limits the search has to respect.
+ * @param stop - Set to end the search early.
+ * @param reporter - Called with the result of every completed iteration.
+ * @param tt - The transposition table, which keeps the positions searched for later searches.
+ * @return The best move found with its score, depth, node count and principal variation.
+ */
+pub fn search(
+    board: &Board,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    reporter: Option<&Reporter>,
+    tt: &mut TranspositionTable,
+) -> SearchResult {
+    run_search(board, limits, stop, reporter, tt, true)
+}
+
+/**
+ * Searches the position for the best move, with or without move ordering.
+ *
+ * Without move ordering the moves are searched in the order they were generated, which is only useful to measure
+ * what the ordering gains.
+ *
+ * @param board - The position to search, with up to date legal moves.
+ * @param limits - The limits the search has to respect.
+ * @param stop - Set to end the search early.
+ * @param reporter - Called with the result of every completed iteration.
+ * @param tt - The transposition table, which keeps the positions searched for later searches.
+ * @param ordering - Whether to order the moves by the move ordering heuristics.
+ * @return The best move found with its score, depth, node count and principal variation.
+ */
+fn run_search(
+    board: &Board,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    reporter: Option<&Reporter>,
+    tt: &mut TranspositionTable,
+    ordering: bool,
+) -> SearchResult {
+    let started = Instant::now();
+    let mut board = board.clone();
+    tt.new_search();
+    let to_move = from_idx(board.board_info.turn);
+    let budget = limits.time_budget(to_move);
+    let mut searcher = Searcher {
+        stop,
+        deadline: budget.map(|budget| started + budget.hard),
+        nodes: 0,
+        iteration: 0,
+        aborted: false,
+        pv: vec![Vec::new(); MAX_PLY],
+        pv_move: None,
+        tt,
+        heuristics: Heuristics::default(),
+        ordering,
+        keys: board
+            .board_info
+            .undo_stack
+            .iter()
+            .map(|state| state.zobrist_key)
+            .collect(),
+    };
+    let mut result = SearchResult {
+        best_move: board.board_info.valid_moves.first().cloned(),
+        ..SearchResult::default()
+    };
+    if result.best_move.is_none() {
+        return result;
+    }
+
+    let max_depth = limits
+        .depth
+        .unwrap_or(MAX_PLY as u32 - 1)
+        .clamp(1, MAX_PLY as u32 - 1);
+    for depth in 1..=max_depth {
+        if depth > 1 && searcher.should_stop_now() {
+            break;
+        }
+        searcher.iteration = depth;
+        let score = searcher.negamax(&mut board, depth, 0, -INFINITY, INFINITY);
+        if searcher.aborted {
+            break;
+        }
+        let pv = pv_moves(&mut board, &searcher.pv[0]);
+        let best_move_changed = depth > 1 && pv.first() != result.best_move.as_ref();
+        let score_drop = if depth > 1 { result.score - score } else { 0 };
+        result.score = score;
+        result.depth = depth;
+        result.nodes = searcher.nodes;
+        result.hashfull = searcher.tt.hashfull();
+        result.pv = pv;
+        result.best_move = result.pv.first().cloned();
+        searcher.pv_move = searcher.pv[0].first().copied();
+        if let Some(reporter) = reporter {
+            reporter(&result);
+        }
+
+        // A mate found within the full search depth cannot be improved on by searching deeper
+        if mate_in(score).is_some() && (MATE - score.abs()) as u32 <= depth {
+            break;
+        }
+        if let Some(budget) = budget {
+            if started.elapsed() >= budget.soft_limit(best_move_changed, score_drop) {
+                break;
+            }
+        }
+    }
+    result.nodes = searcher.nodes;
+    result
+}
+
+/**
+ * Expands a principal variation of packed moves into full moves by playing it out on the board.
+ *
+ * @param board - The position the principal variation starts from; it is left as it was.
+ * @param pv - The packed moves of the principal variation.
+ * @return The full moves of the principal variation.
+ */
+fn pv_moves(board: &mut Board, pv: &[PackedMove]) -> Vec<Move> {
+    let moves: Vec<Move> = pv
+        .iter()
+        .map(|m| {
+            let full = m.to_move(&board.board_info);
+            board.make_move(full.clone());
+            full
+        })
+        .collect();
+    for _ in &moves {
+        board.undo_move();
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::game::player::Color;
+    use crate::rules::r#move::Move;
+    use crate::search::tt::TranspositionTable;
+    use crate::search::{mate_in, search, SearchLimits, MATE};
+    use std::sync::atomic::AtomicBool;
+    use std::time::{Duration, Instant};
+
+    /**
+     * Searches the position given as FEN to the given depth.
+     *
+     * @param fen - The position to search.
+     * @param depth - The maximum depth in plies.
+     * @return The result of the search.
+     */
+    fn search_fen(fen: &str, depth: u32) -> super::SearchResult {
+        let board = Board::new_from_fen(fen).unwrap();
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        };
+        search(
+            &board,
+            &limits,
+            &AtomicBool::new(false),
+            None,
+            &mut TranspositionTable::new(1),
+        )
+    }
+
+    #[test]
+    /**
+     * Tests that the search finds a mate in one and scores it as such.
+     */
+    fn test_mate_in_one() {
+        let result = search_fen(
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+            4,
+        );
+        assert_eq!(result.best_move.as_ref().unwrap().to_uci(), "d8h4");
+        assert_eq!(result.mate_in(), Some(1));
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    /**
+     * Tests that the search finds a mate in two and plays a move of it.
+     */
+    fn test_mate_in_two() {
+        let result = search_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1", 5);
+        assert_eq!(result.mate_in(), Some(2));
+        assert_eq!(result.score, MATE - 3);
+        assert_eq!(result.pv.len(), 3);
+    }
+
+    #[test]
+    /**
+     * Tests that the search wins hanging material.
+     */
+    fn test_winning_capture() {
+        let result = search_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 2);
+        assert_eq!(result.best_move.as_ref().unwrap().to_uci(), "d1d5");
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    /**
+     * Tests that the quiescence search sees the recapture behind the search horizon, so a defended piece is not
+     * taken at the last ply.
+     */
+    fn test_quiescence() {
+        let result = search_fen("4k3/8/2p5/3p4/8/8/3Q4/3RK3 w - - 0 1", 1);
+        assert_ne!(result.best_move.as_ref().unwrap().to_uci(), "d2d5");
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    /**
+     * Tests that the quiescence search does not stand pat in check, so a capture that forks the king and queen
+     * is seen to win the queen.
+     */
+    fn test_quiescence_in_check() {
+        let result = search_fen("3q3k/5p2/8/4N3/2B5/8/8/4K3 w - - 0 1", 1);
+        assert_eq!(result.best_move.as_ref().unwrap().to_uci(), "e5f7");
+        assert!(result.score > 500);
+    }
+
+    #[test]
+    /**
+     * Tests that a player who is lost takes a draw by repeating a position of the game.
+     */
+    fn test_repetition() {
+        let fen = "k7/8/8/8/8/8/qq6/4K1N1 w - - 0 1";
+        assert!(search_fen(fen, 3).score < -1000);
+
+        let mut board = Board::new_from_fen(fen).unwrap();
+        for uci in ["g1f3", "a8b8", "f3g1", "b8a8"] {
+            board.make_move(Move::from_uci(&board, uci).unwrap());
+            board.update();
+        }
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let result = search(
+            &board,
+            &limits,
+            &AtomicBool::new(false),
+            None,
+            &mut TranspositionTable::new(1),
+        );
+        assert_eq!(result.best_move.unwrap().to_uci(), "g1f3");
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    /**
+     * Tests that there is no best move when the player to move has no legal moves.
+     */
+    fn test_no_legal_moves() {
+        let result = search_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1", 3);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    /**
+     * Tests that the depth limit is respected and that the principal variation starts with the best move.
+     */
+    fn test_depth_limit() {
+        let result = search_fen(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            2,
+        );
+        assert_eq!(result.depth, 2);
+        assert!(result.pv.len() <= 2);
+        assert_eq!(result.pv.first(), result.best_move.as_ref());
+        assert!(result.nodes > 20);
+    }
+
+    #[test]
+    /**
+     * Tests that a search with a transposition table filled by an earlier search visits fewer positions and finds
+     * the same move.
+     */
+    fn test_transposition_table() {
+        let board =
+            Board::new_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let stop = AtomicBool::new(false);
+        let mut tt = TranspositionTable::new(4);
+        let cold = search(&board, &limits, &stop, None, &mut tt);
+        let warm = search(&board, &limits, &stop, None, &mut tt);
+        assert!(warm.nodes < cold.nodes / 2);
+        assert_eq!(warm.best_move, cold.best_move);
+        assert_eq!(warm.score, cold.score);
+    }
+
+    #[test]
+    /**
+     * Tests that a stopped search still completes its first iteration.
+     */
+    fn test_stopped_search() {
+        let board = Board::new_standard();
+        let limits = SearchLimits::default();
+        let result = search(
+            &board,
+            &limits,
+            &AtomicBool::new(true),
+            None,
+            &mut TranspositionTable::new(1),
+        );
+        assert_eq!(result.depth, 1);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    /**
+     * Tests that a search on the clock ends within the hard limit of its time budget.
+     */
+    fn test_clock_search() {
+        let board = Board::new_standard();
+        let limits = SearchLimits {
+            wtime: Some(Duration::from_secs(2)),
+            btime: Some(Duration::from_secs(2)),
+            ..SearchLimits::default()
+        };
+        let budget = limits.time_budget(Color::White).unwrap();
+        assert!(budget.soft < budget.hard);
+
+        let started = Instant::now();
+        let mut tt = TranspositionTable::new(1);
+        let result = search(&board, &limits, &AtomicBool::new(false), None, &mut tt);
+        assert!(started.elapsed() < budget.hard + Duration::from_millis(500));
+        assert!(result.depth >= 1);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    /**
+     * Tests the conversion of scores to the number of moves until checkmate.
+     */
+    fn test_mate_in() {
+        assert_eq!(mate_in(MATE - 1), Some(1));
+        assert_eq!(mate_in(MATE - 3), Some(2));
+        assert_eq!(mate_in(-MATE + 2), Some(-1));
+        assert_eq!(mate_in(-MATE), Some(0));
+        assert_eq!(mate_in(250), None);
+        assert_eq!(mate_in(-900), None);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::board::board_info::bb_piece_idx;
+use crate::board::piece::PieceKind;
+use crate::board::{idx, Board};
+use crate::eval::piece_value;
+use crate::rules::move_list::{MoveList, PackedMove, MAX_MOVES};
+use crate::rules::r#move::Move;
+use crate::search::MAX_PLY;
+
+const HASH_MOVE: i32 = 1_000_000; // Score of the stored best move, which is always searched first
+const CAPTURE: i32 = 100_000; // Base score of captures and promotions, ahead of every quiet move
+const KILLER: [i32; 2] = [90_000, 80_000]; // Scores of the first and second killer move of a ply
+const COUNTER_MOVE: i32 = 70_000; // Score of the move that last refuted the opponent's previous move
+const HISTORY_LIMIT: i32 = 60_000; // History scores stay below the counter move score
+
+// Struct to hold what the search learned about good quiet moves, to search them earlier next time
+pub struct Heuristics {
+    killers: Vec<[Option<PackedMove>; 2]>, // Two quiet moves per ply that caused a cutoff
+    history: [[i32; 64]; 12], // Cutoff score per piece kind and color and target square
+    counter_moves: Vec<[Option<PackedMove>; 64]>, // Quiet move that refuted a move, per piece and target square
+}
+
+impl Default for Heuristics {
+    /**
+     * Creates empty move ordering heuristics.
+     *
+     * @return Heuristics without killer moves, history or counter moves.
+     */
+    fn default() -> Self {
+        Self {
+            killers: vec![[None, None]; MAX_PLY],
+            history: [[0; 64]; 12],
+            counter_moves: vec![[None; 64]; 12],
+        }
+    }
+}
+
+impl Heuristics {
+    /**
+     * Remembers a quiet move that caused a beta cutoff.
+     *
+     * The move becomes the first killer move of its ply, the counter move to the opponent's previous move, and
+     * its history score grows with the square of the remaining depth.
+     *
+     * @param board - The position the move was played in.
+     * @param m - The quiet move that caused the cutoff.
+     * @param depth - The remaining depth of the search that cut off.
+     * @param ply - The distance from the root of the position the move was played in.
+     */
+    pub fn record_cutoff(&mut self, board: &Board, m: PackedMove, depth: u32, ply: usize) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(m) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(m);
+        }
+
+        if let Some(piece) = moving_piece_idx(board, m) {
+            let history = &mut self.history[piece][m.to_square()];
+            *history += (depth * depth) as i32;
+            if *history >= HISTORY_LIMIT {
+                self.history
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|score| *score /= 2);
+            }
+        }
+
+        if let Some(previous) = board.board_info.move_history.last() {
+            self.counter_moves[piece_idx(previous)][idx(previous.to)] = Some(m);
+        }
+    }
+
+    /**
+     * Scores a move for ordering; higher scores are searched first.
+     *
+     * The stored best move comes first, then captures and promotions by MVV-LVA, then killer moves, the counter
+     * move and finally the other quiet moves by their history score.
+     *
+     * @param board - The position the move is played in.
+     * @param m - The move to score.
+     * @param hash_move - The best move stored for the position, if any.
+     * @param previous - The opponent's move before it, if any.
+     * @param ply - The distance from the root of the position.
+     * @return The ordering score of the move.
+     */
+    pub fn score(
+        &self,
+        board: &Board,
+        m: PackedMove,
+        hash_move: Option<PackedMove>,
+        previous: Option<&Move>,
+        ply: usize,
+    ) -> i32 {
+        if hash_move == Some(m) {
+            return HASH_MOVE;
+        }
+        if m.is_capture() || m.promotion_kind().is_some() {
+            return CAPTURE + mvv_lva(board, m);
+        }
+        if let Some(i) = self.killers[ply].iter().position(|&k| k == Some(m)) {
+            return KILLER[i];
+        }
+        let counter = previous.and_then(|p| self.counter_moves[piece_idx(p)][idx(p.to)]);
+        if counter == Some(m) {
+            return COUNTER_MOVE;
+        }
+        moving_piece_idx(board, m).map_or(0, |piece| self.history[piece][m.to_square()])
+    }
+
+    /**
+     * Sorts moves so that the most promising ones are searched first.
+     *
+     * Moves with the same score keep the order they were generated in.
+     *
+     * @param board - The position the moves are played in.
+     * @param moves - The moves to sort in place.
+     * @param hash_move - The best move stored for the position, if any.
+     * @param ply - The distance from the root of the position.
+     */
+    pub fn order(
+        &self,
+        board: &Board,
+        moves: &mut MoveList,
+        hash_move: Option<PackedMove>,
+        ply: usize,
+    ) {
+        let previous = board.board_info.move_history.last();
+        let moves = moves.as_mut_slice();
+        let mut scores = [0; MAX_MOVES];
+        for (i, &m) in moves.iter().enumerate() {
+            scores[i] = self.score(board, m, hash_move, previous, ply);
+        }
+
+        // Insertion sort, as move lists are short and must not allocate
+        for i in 1..moves.len() {
+            let (score, m) = (scores[i], moves[i]);
+            let mut j = i;
+            while j > 0 && scores[j - 1] < score {
+                scores[j] = scores[j - 1];
+                moves[j] = moves[j - 1];
+                j -= 1;
+            }
+            scores[j] = score;
+            moves[j] = m;
+        }
+    }
+}
+
+/**
+ * Returns the index of the moving piece's kind and color into the history and counter move tables.
+ *
+ * @param m - The move.
+ * @return The piece index, as used for the piece bitboards.
+ */
+fn piece_idx(m: &Move) -> usize {
+    bb_piece_idx(m.from_piece.kind, m.from_piece.color)
+}
+
+/**
+ * Returns the index of the piece making a packed move into the history table.
+ *
+ * @param board - The position the move is played in.
+ * @param m - The move.
+ * @return The piece index, as used for the piece bitboards, or None if the from square is empty.
+ */
+fn moving_piece_idx(board: &Board, m: PackedMove) -> Option<usize> {
+    board.squares[m.from_square()].map(|piece| bb_piece_idx(piece.kind, piece.color))
+}
+
+/**
+ * Returns the piece a move captures.
+ *
+ * @param board - The position the move is played in.
+ * @param m - The move.
+ * @return The kind of the captured piece, or None if the move is not a capture.
+ */
+pub fn victim(board: &Board, m: PackedMove) -> Option<PieceKind> {
+    if m.is_en_passant() {
+        Some(PieceKind::Pawn)
+    } else {
+        board.squares[m.to_square()].map(|piece| piece.kind)
+    }
+}
+
+/**
+ * Scores a capture by Most Valuable Victim - Least Valuable Attacker.
+ *
+ * Taking a more valuable piece is always preferred, and among captures of the same piece the one with the
+ * cheapest attacker. Promotions add the value the pawn gains.
+ *
+ * @param board - The position the move is played in.
+ * @param m - The capture or promotion.
+ * @return The MVV-LVA score, higher for better captures.
+ */
+pub fn mvv_lva(board: &Board, m: PackedMove) -> i32 {
+    let victim = victim(board, m).map_or(0, piece_value);
+    let promotion = m
+        .promotion_kind()
+        .map_or(0, |kind| piece_value(kind) - piece_value(PieceKind::Pawn));
+    let attacker = board.squares[m.from_square()].map_or(0, |piece| piece_value(piece.kind));
+    (victim + promotion) * 10 - attacker / 10
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::game::player::from_idx;
+    use crate::rules::generate_legal_moves;
+    use crate::rules::move_list::{MoveList, PackedMove};
+    use crate::rules::r#move::Move;
+    use crate::search::ordering::{mvv_lva, Heuristics};
+
+    /**
+     * Orders the legal moves of a position and returns them in UCI notation.
+     *
+     * @param heuristics - The heuristics to order the moves by.
+     * @param board - The position to order the moves of.
+     * @param hash_move - The best move stored for the position, if any.
+     * @param ply - The distance from the root of the position.
+     * @return The moves in UCI notation, best first.
+     */
+    fn ordered(
+        heuristics: &Heuristics,
+        board: &Board,
+        hash_move: Option<PackedMove>,
+        ply: usize,
+    ) -> Vec<String> {
+        let mut moves = MoveList::new();
+        generate_legal_moves(
+            &board.board_info,
+            from_idx(board.board_info.turn),
+            &mut moves,
+        );
+        heuristics.order(board, &mut moves, hash_move, ply);
+        moves
+            .iter()
+            .map(|m| m.to_move(&board.board_info).to_uci())
+            .collect()
+    }
+
+    #[test]
+    /**
+     * Tests that captures are ordered by victim first and attacker second, ahead of quiet moves.
+     */
+    fn test_mvv_lva() {
+        let board = Board::new_from_fen("4k3/8/2r5/1P1q4/4P3/2N5/8/4K3 w - - 0 1").unwrap();
+        let score = |uci: &str| {
+            mvv_lva(
+                &board,
+                PackedMove::from(&Move::from_uci(&board, uci).unwrap()),
+            )
+        };
+        assert!(score("e4d5") > score("c3d5"));
+        assert!(score("c3d5") > score("b5c6"));
+
+        let first = ordered(&Heuristics::default(), &board, None, 0);
+        assert_eq!(first[..3], ["e4d5", "c3d5", "b5c6"]);
+    }
+
+    #[test]
+    /**
+     * Tests that the hash move, killer moves, counter moves and history change the order of quiet moves.
+     */
+    fn test_quiet_move_heuristics() {
+        let mut board = Board::new_standard();
+        let mut heuristics = Heuristics::default();
+        let quiet =
+            |board: &Board, uci: &str| PackedMove::from(&Move::from_uci(board, uci).unwrap());
+        let (a3, h3, g1f3) = (
+            quiet(&board, "a2a3"),
+            quiet(&board, "h2h3"),
+            quiet(&board, "g1f3"),
+        );
+        assert_eq!(ordered(&heuristics, &board, Some(h3), 1)[0], "h2h3");
+
+        heuristics.record_cutoff(&board, a3, 2, 1);
+        heuristics.record_cutoff(&board, h3, 1, 1);
+        assert_eq!(ordered(&heuristics, &board, None, 1)[..2], ["h2h3", "a2a3"]);
+
+        // History carries over to other plies
+        heuristics.record_cutoff(&board, g1f3, 3, 5);
+        assert_eq!(
+            ordered(&heuristics, &board, None, 1)[..3],
+            ["h2h3", "a2a3", "g1f3"]
+        );
+        assert_eq!(heuristics.score(&board, g1f3, None, None, 2), 9);
+
+        // The counter move answers the opponent's last move
+        board.make_move(Move::from_uci(&board, "e2e4").unwrap());
+        board.update();
+        let e7e5 = quiet(&board, "e7e5");
+        heuristics.record_cutoff(&board, e7e5, 1, 4);
+        assert_eq!(ordered(&heuristics, &board, None, 1)[0], "e7e5");
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+const DEFAULT_MOVES_TO_GO: u32 = 30; // Moves the remaining time is shared between without a time control
+const HARD_LIMIT_FACTOR: u32 = 4; // How many soft limits a single search may take at most
+const PANIC_EXTENSION: u32 = 100; // Extra percent of the soft limit when the best move just changed
+const MAX_SCORE_DROP_EXTENSION: i32 = 100; // Most extra percent of the soft limit for a falling score
+
+// Struct to hold how long a search may take
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeBudget {
+    pub soft: Duration, // No new iteration is started after this time, unless the position is volatile
+    pub hard: Duration, // The search is stopped after this time, even in the middle of an iteration
+}
+
+impl TimeBudget {
+    /**
+     * Creates a budget that uses exactly the given time.
+     *
+     * @param time - The time to spend on the move.
+     * @return A budget whose soft and hard limits are both the given time.
+     */
+    pub fn fixed(time: Duration) -> Self {
+        Self {
+            soft: time,
+            hard: time,
+        }
+    }
+
+    /**
+     * Allocates time for a move from the time left on the clock.
+     *
+     * The soft limit is an equal share of the remaining time for the moves until the next time control, plus most
+     * of the increment. The hard limit allows a few times as much. Neither uses more than half of the remaining
+     * time, unless this is the last move before the time control. The move overhead is kept in reserve.
+     *
+     * @param time - The time left on the clock.
+     * @param increment - The time added to the clock after the move.
+     * @param movestogo - The moves left until the next time control, or None if the rest of the game is played
+     *                    in the remaining time.
+     * @param overhead - The time kept in reserve for communication delays.
+     * @return The budget for the move.
+     */
+    pub fn allocate(
+        time: Duration,
+        increment: Duration,
+        movestogo: Option<u32>,
+        overhead: Duration,
+    ) -> Self {
+        let available = time.saturating_sub(overhead);
+        let moves_left = movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let most = if moves_left == 1 {
+            available
+        } else {
+            available / 2
+        };
+        let soft = (time / moves_left + increment * 3 / 4)
+            .saturating_sub(overhead)
+            .min(most);
+        Self {
+            soft,
+            hard: (soft * HARD_LIMIT_FACTOR).min(most),
+        }
+    }
+
+    /**
+     * Returns the time after which no new iteration is started, extended when the position is volatile.
+     *
+     * When the best move changed in the last iteration the soft limit is doubled (panic time), and a score that
+     * fell since the iteration before adds up to the soft limit again. The result never exceeds the hard limit.
+     *
+     * @param best_move_changed - Whether the last iteration found a different best move than the one before.
+     * @param score_drop - How many centipawns the score fell in the last iteration; negative if it rose.
+     * @return The extended soft limit.
+     */
+    pub fn soft_limit(&self, best_move_changed: bool, score_drop: i32) -> Duration {
+        let mut percent = 100;
+        if best_move_changed {
+            percent += PANIC_EXTENSION;
+        }
+        percent += score_drop.clamp(0, MAX_SCORE_DROP_EXTENSION) as u32;
+        (self.soft * percent / 100).min(self.hard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::time::TimeBudget;
+    use std::time::Duration;
+
+    #[test]
+    /**
+     * Tests the soft and hard limits allocated from the clock, the increment and the moves to go.
+     */
+    fn test_allocate() {
+        let ms = Duration::from_millis;
+        let budget = TimeBudget::allocate(ms(60_000), ms(0), None, ms(0));
+        assert_eq!(budget.soft, ms(2000));
+        assert_eq!(budget.hard, ms(8000));
+
+        let budget = TimeBudget::allocate(ms(60_000), ms(1000), None, ms(50));
+        assert_eq!(budget.soft, ms(2700));
+        assert_eq!(budget.hard, ms(10_800));
+
+        // The last move before the time control may use all of the time but the overhead
+        let budget = TimeBudget::allocate(ms(5000), ms(0), Some(1), ms(100));
+        assert_eq!(budget.soft, ms(4900));
+        assert_eq!(budget.hard, ms(4900));
+
+        // Short on time, no more than half of the clock is used
+        let budget = TimeBudget::allocate(ms(1000), ms(2000), None, ms(0));
+        assert_eq!(budget.soft, ms(500));
+        assert_eq!(budget.hard, ms(500));
+        let budget = TimeBudget::allocate(ms(3000), ms(200), None, ms(0));
+        assert_eq!(budget.soft, ms(250));
+        assert_eq!(budget.hard, ms(1000));
+    }
+
+    #[test]
+    /**
+     * Tests that the soft limit is extended when the best move changes or the score falls, up to the hard limit.
+     */
+    fn test_soft_limit_extensions() {
+        let ms = Duration::from_millis;
+        let budget = TimeBudget {
+            soft: ms(1000),
+            hard: ms(2500),
+        };
+        assert_eq!(budget.soft_limit(false, 0), ms(1000));
+        assert_eq!(budget.soft_limit(false, -80), ms(1000));
+        assert_eq!(budget.soft_limit(false, 40), ms(1400));
+        assert_eq!(budget.soft_limit(true, 0), ms(2000));
+        assert_eq!(budget.soft_limit(true, 300), ms(2500));
+
+        let fixed = TimeBudget::fixed(ms(500));
+        assert_eq!(fixed.soft_limit(true, 100), ms(500));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::rules::move_list::PackedMove;
+use crate::search::{MATE, MAX_PLY};
+use std::mem::size_of;
+
+pub const DEFAULT_HASH_MB: usize = 16; // Size of a new transposition table in megabytes
+
+// Enum to represent how a stored score relates to the true score of the position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact, // The score is the true score
+    Lower, // The true score is at least the score, as the search failed high
+    Upper, // The true score is at most the score, as the search failed low
+}
+
+// Struct to hold what a search found out about a position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    pub key: u64,                      // Zobrist key of the position
+    pub depth: u32,                    // Depth the position was searched to
+    pub score: i32,   // Score with mate scores counted from the position, not the root
+    pub bound: Bound, // How the score relates to the true score
+    pub best_move: Option<PackedMove>, // Best move found, or None if every move failed low
+    pub age: u8,      // Search that stored the entry
+}
+
+// Struct to hold the entries of one slot of the table
+#[derive(Clone, Debug, Default)]
+struct Bucket {
+    depth_preferred: Option<Entry>, // Only replaced by deeper searches or entries from older searches
+    always_replace: Option<Entry>,  // Replaced by every entry the other slot turns down
+}
+
+// Struct to hold searched positions by their Zobrist key
+#[derive(Debug)]
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>, // Fixed number of buckets, indexed by the key
+    age: u8,              // Current search, used to replace entries of earlier searches first
+}
+
+impl Default for TranspositionTable {
+    /**
+     * Creates a transposition table of the default size.
+     *
+     * @return A new, empty transposition table of `DEFAULT_HASH_MB` megabytes.
+     */
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    /**
+     * Creates an empty transposition table using about the given amount of memory.
+     *
+     * @param megabytes - The memory to use in megabytes; the table always holds at least one bucket.
+     * @return A new, empty transposition table.
+     */
+    pub fn new(megabytes: usize) -> Self {
+        let count = (megabytes * 1024 * 1024 / size_of::<Bucket>()).max(1);
+        Self {
+            buckets: vec![Bucket::default(); count],
+            age: 0,
+        }
+    }
+
+    /**
+     * Returns the number of entries the table can hold.
+     *
+     * @return The capacity of the table in entries.
+     */
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 2
+    }
+
+    /**
+     * Removes all entries, as they are of no use in a new game.
+     */
+    pub fn clear(&mut self) {
+        self.buckets.fill(Bucket::default());
+        self.age = 0;
+    }
+
+    /**
+     * Starts a new search, so entries stored from now on replace the entries of earlier searches first.
+     */
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    /**
+     * Looks up the entry of a position.
+     *
+     * The best move is stored packed, so it only becomes a full move once it is matched against the moves of
+     * the position.
+     *
+     * @param key - The Zobrist key of the position.
+     * @return The entry stored for the position, or None if it is not in the table.
+     */
+    pub fn probe(&self, key: u64) -> Option<&Entry> {
+        let bucket = &self.buckets[self.index(key)];
+        [&bucket.depth_preferred, &bucket.always_replace]
+            .into_iter()
+            .flatten()
+            .find(|entry| entry.key == key)
+    }
+
+    /**
+     * Stores what a search found out about a position.
+     *
+     * The entry goes into the depth-preferred slot if that slot is empty, holds the same position, was stored by
+     * an earlier search or was searched less deeply. An older entry of the same position in the always-replace
+     * slot is then removed, so the bucket never holds the position twice. Otherwise the entry goes into the
+     * always-replace slot.
+     *
+     * @param key - The Zobrist key of the position.
+     * @param depth - The depth the position was searched to.
+     * @param score - The score of the position, with mate scores counted from the root.
+     * @param bound - How the score relates to the true score.
+     * @param best_move - The best move found, if any.
+     * @param ply - The distance of the position from the root, used to store mate scores relative to it.
+     */
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        score: i32,
+        bound: Bound,
+        best_move: Option<PackedMove>,
+        ply: usize,
+    ) {
+        let age = self.age;
+        let idx = self.index(key);
+        let bucket = &mut self.buckets[idx];
+        let entry = Entry {
+            key,
+            depth,
+            score: score_to_tt(score, ply),
+            bound,
+            best_move,
+            age,
+        };
+        let replace_deep = bucket
+            .depth_preferred
+            .as_ref()
+            .is_none_or(|old| old.key == key || old.age != age || depth >= old.depth);
+        if replace_deep {
+            if bucket.always_replace.is_some_and(|old| old.key == key) {
+                bucket.always_replace = None;
+            }
+            bucket.depth_preferred = Some(entry);
+        } else {
+            bucket.always_replace = Some(entry);
+        }
+    }
+
+    /**
+     * Estimates how full the table is from a sample of its buckets.
+     *
+     * @return The permille of sampled entries used by the current search.
+     */
+    pub fn hashfull(&self) -> u32 {
+        let sample = &self.buckets[..self.buckets.len().min(500)];
+        let used = sample
+            .iter()
+            .flat_map(|bucket| [&bucket.depth_preferred, &bucket.always_replace])
+            .flatten()
+            .filter(|entry| entry.age == self.age)
+            .count();
+        (used * 1000 / (sample.len() * 2)) as u32
+    }
+
+    /**
+     * Returns the index of the bucket a position is stored in.
+     *
+     * @param key - The Zobrist key of the position.
+     * @return The index of the bucket.
+     */
+    fn index(&self, key: u64) -> usize {
+        ((key as u128 * self.buckets.len() as u128) >> 64) as usize
+    }
+}
+
+/**
+ * Converts a score counted from the root into a score counted from the position, for storing.
+ *
+ * Mate scores count the distance to mate from the root. The same position can be reached at other distances from
+ * the root, so the table stores the distance to mate from the position itself.
+ *
+ * @param score - The score with mate scores counted from the root.
+ * @param ply - The distance of the position from the root.
+ * @return The score with mate scores counted from the position.
+ */
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -MATE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+/**
+ * Converts a stored score counted from the position back into a score counted from the root.
+ *
+ * @param score - The stored score with mate scores counted from the position.
+ * @param ply - The distance of the position from the root.
+ * @return The score with mate scores counted from the root.
+ */
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::rules::move_list::PackedMove;
+    use crate::search::tt::{score_from_tt, score_to_tt, Bound, Entry, TranspositionTable};
+    use crate::search::MATE;
+    use std::mem::size_of;
+
+    #[test]
+    /**
+     * Tests storing and probing entries, including the packed best move.
+     */
+    fn test_store_and_probe() {
+        let board = Board::new_standard();
+        let best_move = board.board_info.valid_moves.first().map(PackedMove::from);
+        let mut tt = TranspositionTable::new(1);
+        assert!(tt.capacity() > 1000);
+        assert!(tt.probe(42).is_none());
+
+        tt.store(42, 3, 25, Bound::Exact, best_move, 0);
+        let entry = tt.probe(42).unwrap();
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.score, 25);
+        assert_eq!(entry.bound, Bound::Exact);
+        assert_eq!(entry.best_move, best_move);
+        assert!(tt.probe(43).is_none());
+        assert!(size_of::<Entry>() <= 24);
+    }
+
+    #[test]
+    /**
+     * Tests that deeper entries are kept while shallower ones use the always-replace slot, and that entries of
+     * earlier searches are replaced first.
+     */
+    fn test_replacement() {
+        let mut tt = TranspositionTable::new(0);
+        tt.store(1, 6, 10, Bound::Exact, None, 0);
+        tt.store(2, 2, 20, Bound::Lower, None, 0);
+        assert_eq!(tt.probe(1).unwrap().depth, 6);
+        assert_eq!(tt.probe(2).unwrap().depth, 2);
+
+        // A third shallow position takes the always-replace slot
+        tt.store(3, 1, 30, Bound::Upper, None, 0);
+        assert!(tt.probe(1).is_some());
+        assert!(tt.probe(2).is_none());
+        assert!(tt.probe(3).is_some());
+
+        // After a new search the deep entry can be replaced by a shallow one
+        tt.new_search();
+        tt.store(4, 1, 40, Bound::Exact, None, 0);
+        assert!(tt.probe(1).is_none());
+        assert_eq!(tt.probe(4).unwrap().score, 40);
+
+        // A deeper search of the position in the always-replace slot moves it to the depth-preferred slot
+        tt.store(3, 2, 50, Bound::Exact, None, 0);
+        let bucket = &tt.buckets[tt.index(3)];
+        assert_eq!(bucket.depth_preferred.unwrap().score, 50);
+        assert!(bucket.always_replace.is_none());
+    }
+
+    #[test]
+    /**
+     * Tests that mate scores are stored relative to the position and restored relative to the root.
+     */
+    fn test_mate_score_adjustment() {
+        assert_eq!(score_to_tt(MATE - 5, 3), MATE - 2);
+        assert_eq!(score_from_tt(MATE - 2, 3), MATE - 5);
+        assert_eq!(score_from_tt(MATE - 2, 1), MATE - 3);
+        assert_eq!(score_to_tt(-MATE + 4, 4), -MATE);
+        assert_eq!(score_from_tt(-MATE, 2), -MATE + 2);
+        assert_eq!(score_to_tt(150, 7), 150);
+        assert_eq!(score_from_tt(-150, 7), -150);
+
+        let mut tt = TranspositionTable::new(1);
+        tt.store(7, 4, MATE - 5, Bound::Exact, None, 3);
+        assert_eq!(score_from_tt(tt.probe(7).unwrap().score, 1), MATE - 3);
+    }
+
+    #[test]
+    /**
+     * Tests that the table reports how full it is and that clearing it removes every entry.
+     */
+    fn test_hashfull_and_clear() {
+        let mut tt = TranspositionTable::new(1);
+        assert_eq!(tt.hashfull(), 0);
+        let capacity = tt.capacity() as u64;
+        for key in 0..capacity {
+            let depth = (capacity - key) as u32;
+            let key = key.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            tt.store(key, depth, 0, Bound::Exact, None, 0);
+        }
+        assert!(tt.hashfull() > 900);
+
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+
+        tt.store(5, 1, 0, Bound::Exact, None, 0);
+        tt.clear();
+        assert!(tt.probe(5).is_none());
+    }
+}